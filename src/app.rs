@@ -1,83 +1,106 @@
-#[cfg(target_os = "windows")]
 use eframe::egui;
 use std::path::PathBuf;
 use std::fs;
 use std::thread;
 use std::sync::mpsc;
-use chrono::{DateTime, Local};
-use crate::logic::IdCrypto;
+use resigner::batch::{self, BatchOptions, BatchReport, ConflictPolicy, FileStatus, Operation, Plan};
+use resigner::games::{GameProfile, IdFormat, Platform, PlatformProfile, Registry, USER_GAMES_FILE};
+use resigner::identify::{self, Detection, EncryptionState, FileState, Owner};
+use resigner::ids::{self, SteamId};
+use resigner::locate::{self, SaveSlot};
+use resigner::history::{self, History, RunRecord, HISTORY_FILE};
+use resigner::steam::{SteamAccount, local_steam_accounts, local_steam_ids};
 
 #[derive(Debug, Clone, PartialEq)]
 enum Mode {
     Resign,
     Decrypt,
     Encrypt,
+    Verify,
+    Rename,
+    Convert,
 }
 
 #[derive(Debug, Clone)]
 enum Status {
     Idle,
     Processing,
-    Completed(String),
+    Completed(BatchReport),
     Error(String),
-    EncryptionWarning(PathBuf, PathBuf, String, String),
+    Info(String),
+    Review(Vec<FileState>, Option<PendingBatch>),
+    Preview(Plan),
+    Slots(Vec<SaveSlot>),
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
-struct AppConfig {
-    output_dir: String,
+// What a worker thread sends back when it's done
+enum WorkResult {
+    Batch(Result<BatchReport, String>),
+    // Files to look at before the pending batch, if any, is started
+    Review(Result<Vec<FileState>, String>, Option<PendingBatch>),
+    // Every file was as expected, so the batch can start
+    Ready(PendingBatch),
+    Identified(Result<Option<Owner>, String>, usize),
+    Detected(Result<Option<Detection>, String>, String),
 }
 
 #[derive(Debug, Clone)]
-struct GameInfo {
-    name: &'static str,
-    code: &'static str,
-    platforms: &'static [Platform],
+struct PendingBatch {
+    input: PathBuf,
+    output: PathBuf,
+    code: String,
+    op: Operation,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum Platform {
-    Steam,
-    GOG,
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+struct AppConfig {
+    output_dir: String,
+    continue_on_error: bool,
+    copy_skipped: bool,
+    conflicts: ConflictPolicy,
+    redact_ids: bool,
 }
 
-const GAMES: &[GameInfo] = &[
-    GameInfo { 
-        name: "DOOM Eternal", 
-        code: "MANCUBUS",
-        platforms: &[Platform::Steam, Platform::GOG],
-    },
-    GameInfo { 
-        name: "DOOM: The Dark Ages", 
-        code: "MANCUBUS",
-        platforms: &[Platform::Steam],
-        // platforms: &[Platform::Steam, Platform::GOG],
-    },
-    GameInfo { 
-        name: "Indiana Jones and the Great Circle", 
-        code: "SUKHOTHAI",
-        platforms: &[Platform::Steam, Platform::GOG],
-    },
-];
-
 pub struct SaveDataApp {
     mode: Mode,
+    registry: Registry,
     game_idx: usize,
     input_dir: String,
     output_dir: String,
+    continue_on_error: bool,
+    copy_skipped: bool,
+    conflicts: ConflictPolicy,
+    redact_ids: bool,
+    in_place: bool,
     steam_id: String,
     old_id: String,
     new_id: String,
     status: Status,
-    progress_rx: Option<mpsc::Receiver<String>>,
+    progress_rx: Option<mpsc::Receiver<WorkResult>>,
     active_tab: Tab,
     config_file: PathBuf,
     platform: Platform,
+    candidates: String,
+    use_local_steam: bool,
+    identified: Option<Result<Owner, String>>,
+    identifying: bool,
+    logical_names: String,
+    rename_from: String,
+    rename_to: String,
+    target_platform: Platform,
+    steam_accounts: Vec<SteamAccount>,
+    history_file: PathBuf,
+    history: Vec<RunRecord>,
+    history_status: Option<Result<String, String>>,
+    force_restore: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum Tab {
     Main,
+    Identify,
+    History,
     Settings,
 }
 
@@ -86,23 +109,62 @@ impl SaveDataApp {
     pub fn new() -> Self {
         let config_file = PathBuf::from("resigner_config");
         let config = Self::load_config(&config_file);
+        let (registry, status) = match Registry::load(&PathBuf::from(USER_GAMES_FILE)) {
+            Ok(registry) => (registry, Status::Idle),
+            Err(e) => (Registry::builtin(), Status::Error(e.to_string())),
+        };
 
-        Self {
+        let mut app = Self {
             mode: Mode::Resign,
+            registry,
             game_idx: 0,
             input_dir: String::new(),
             output_dir: config.output_dir,
+            continue_on_error: config.continue_on_error,
+            copy_skipped: config.copy_skipped,
+            conflicts: config.conflicts,
+            redact_ids: config.redact_ids,
+            in_place: false,
             steam_id: String::new(),
             old_id: String::new(),
             new_id: String::new(),
-            status: Status::Idle,
+            status,
             progress_rx: None,
             active_tab: Tab::Main,
-            config_file: config_file,
+            config_file,
             platform: Platform::Steam,
+            candidates: String::new(),
+            use_local_steam: true,
+            identified: None,
+            identifying: false,
+            logical_names: String::new(),
+            rename_from: String::new(),
+            rename_to: String::new(),
+            target_platform: Platform::GOG,
+            steam_accounts: local_steam_accounts(),
+            history_file: PathBuf::from(HISTORY_FILE),
+            history: Vec::new(),
+            history_status: None,
+            force_restore: false,
+        };
+        app.load_history();
+        app
+    }
+
+    fn load_history(&mut self) {
+        match History::load(&self.history_file) {
+            Ok(history) => self.history = history.runs,
+            Err(e) => self.history_status = Some(Err(e.to_string())),
         }
     }
 
+    fn restore_run(&mut self, run: &RunRecord) {
+        let result = history::restore(run, self.force_restore)
+            .and_then(|msg| History::mark_restored(&self.history_file, &run.id).map(|_| msg));
+        self.history_status = Some(result.map_err(|e| e.to_string()));
+        self.load_history();
+    }
+
     fn load_config(config_path: &PathBuf) -> AppConfig {
         if let Ok(content) = fs::read_to_string(config_path) {
             serde_json::from_str(&content).unwrap_or_default()
@@ -114,6 +176,10 @@ impl SaveDataApp {
     fn save_config(&self) {
         let config = AppConfig {
             output_dir: self.output_dir.clone(),
+            continue_on_error: self.continue_on_error,
+            copy_skipped: self.copy_skipped,
+            conflicts: self.conflicts,
+            redact_ids: self.redact_ids,
         };
 
         if let Ok(json) = serde_json::to_string_pretty(&config) {
@@ -121,457 +187,477 @@ impl SaveDataApp {
         }
     }
 
-    fn get_suffix(&self) -> &'static str {
+    fn get_operation(&self) -> Operation {
         match self.mode {
-            Mode::Resign => "_resigned",
-            Mode::Decrypt => "_decrypted",
-            Mode::Encrypt => "_encrypted",
+            Mode::Resign => Operation::Resign { old_id: self.old_id.clone(), new_id: self.new_id.clone() },
+            Mode::Decrypt => Operation::Decrypt { id: self.steam_id.clone() },
+            Mode::Encrypt => Operation::Encrypt { id: self.steam_id.clone() },
+            Mode::Verify => Operation::Verify { id: self.steam_id.clone() },
+            Mode::Rename => Operation::Rename {
+                old_id: self.old_id.clone(),
+                new_id: self.get_rename_id().to_string(),
+                from: self.rename_from.trim().to_string(),
+                to: self.rename_to.trim().to_string(),
+            },
+            Mode::Convert => Operation::Convert {
+                old_id: self.old_id.clone(),
+                new_id: self.new_id.clone(),
+                to_code: self.get_target_profile().map(|p| p.code.clone()).unwrap_or_default(),
+            },
         }
     }
 
-    fn get_game_code(&self) -> String {
-        match (self.game_idx, &self.platform) {
-            (0, Platform::GOG) => "ARACHNOTRON".to_string(),
-            // (1, Platform::GOG) => "not_on_gog".to_string(), // for future
-            (2, Platform::GOG) => "PAINELEMENTAL".to_string(),
-            _ => GAMES[self.game_idx].code.to_string(),
+    // An empty New ID keeps the saves on the same account
+    fn get_rename_id(&self) -> &str {
+        if self.new_id.is_empty() {
+            &self.old_id
+        } else {
+            &self.new_id
         }
     }
 
-    fn get_id_label(&self) -> &'static str {
-        match &self.platform {
-            Platform::GOG => "Galaxy ID",
-            Platform::Steam => "SteamID",
-        }
+    fn get_game(&self) -> &GameProfile {
+        &self.registry.games()[self.game_idx]
     }
 
-    fn get_final_output_path(&self) -> PathBuf {
-        let base = if self.output_dir.is_empty() {
-            let input = PathBuf::from(&self.input_dir);
-            input.parent().unwrap_or(&input).to_path_buf()
-        } else {
-            PathBuf::from(&self.output_dir)
-        };
-        
-        let input = PathBuf::from(&self.input_dir);
-        let name = input.file_name()
-            .unwrap_or_else(|| std::ffi::OsStr::new("output"))
-            .to_string_lossy();
-        
-        base.join(format!("{}{}", name, self.get_suffix()))
+    // Falls back to the game's first platform if the selected one isn't supported
+    fn get_platform_profile(&self) -> &PlatformProfile {
+        let game = self.get_game();
+        game.platform(&self.platform).unwrap_or(&game.platforms[0])
     }
 
-    fn validate_steam_id(id: &str) -> Result<(), String> {
-        if id.is_empty() {
-            return Err("SteamID cannot be empty".to_string());
-        }
-        
-        if id.len() != 17 {
-            return Err("SteamID must be exactly 17 digits long".to_string());
-        }
-        
-        if !id.chars().all(|c| c.is_ascii_digit()) {
-            return Err("SteamID must contain only numbers".to_string());
-        }
-        
-        let num: u64 = id.parse().map_err(|_| "Invalid SteamID format")?;
-        
-        if !id.starts_with("7656119") {
-            return Err("SteamID must start with 7656119 (Steam64 format)".to_string());
-        }
-        
-        if num < 76561197960265728 {
-            return Err("SteamID appears to be invalid (too small for Steam64 format)".to_string());
-        }
-        
-        if num > 76561999999999999 {
-            return Err("SteamID appears to be invalid (too large for Steam64 format)".to_string());
-        }
-        
-        Ok(())
+    fn get_game_code(&self) -> String {
+        self.get_platform_profile().code.clone()
     }
 
-    fn is_file_encrypted(data: &[u8]) -> bool {
-        if data.len() < 16 {
-            return false;
-        }
-        
-        let text_start = std::str::from_utf8(&data[..16.min(data.len())]).is_ok();
-        if text_start {
-            return false;
-        }
-        
-        let mut byte_counts = [0u32; 256];
-        for &byte in data.iter().take(1024) {
-            byte_counts[byte as usize] += 1;
+    // The platform Convert writes to, if the game has one besides the selected platform
+    fn get_target_profile(&self) -> Option<&PlatformProfile> {
+        Some(self.target_platform.clone())
+            .filter(|p| *p != self.platform)
+            .and_then(|p| self.get_game().platform(&p))
+    }
+
+    fn get_id_label(&self) -> &'static str {
+        self.get_platform_profile().id_format.label()
+    }
+
+    fn get_final_output_path(&self) -> PathBuf {
+        if self.in_place {
+            return PathBuf::from(&self.input_dir);
         }
-        
-        let len = data.len().min(1024) as f64;
-        let entropy: f64 = byte_counts.iter()
-            .filter(|&&count| count > 0)
-            .map(|&count| {
-                let p = count as f64 / len;
-                -p * p.log2()
-            })
-            .sum();
-        
-        entropy > 6.0
+        let base = (!self.output_dir.is_empty()).then(|| PathBuf::from(&self.output_dir));
+        batch::output_dir(&PathBuf::from(&self.input_dir), base.as_deref(), self.get_operation().suffix())
     }
 
-    fn process_files(&mut self) {
+    // Checks an ID field against the platform's format and rewrites it in
+    // canonical form (Steam IDs entered in any common form become SteamID64)
+    fn normalize_id(profile: &PlatformProfile, field: &mut String, label: &str) -> Result<(), String> {
+        *field = ids::normalize_id(profile, field)
+            .map_err(|e| format!("Invalid {}{}: {}", label, profile.id_format.label(), e))?;
+        Ok(())
+    }
+
+    fn validate_ids(&mut self) -> Result<(), String> {
+        let source = self.get_platform_profile().clone();
         match self.mode {
-            Mode::Decrypt | Mode::Encrypt => {
-                if self.platform == Platform::Steam {
-                    if let Err(e) = Self::validate_steam_id(&self.steam_id) {
-                        self.status = Status::Error(format!("Invalid SteamID: {}", e));
-                        return;
-                    }
-                }
+            Mode::Decrypt | Mode::Encrypt | Mode::Verify => {
+                Self::normalize_id(&source, &mut self.steam_id, "")?;
             },
             Mode::Resign => {
-                if self.platform == Platform::Steam {
-                    if let Err(e) = Self::validate_steam_id(&self.old_id) {
-                        self.status = Status::Error(format!("Invalid Old SteamID: {}", e));
-                        return;
-                    }
-                    if let Err(e) = Self::validate_steam_id(&self.new_id) {
-                        self.status = Status::Error(format!("Invalid New SteamID: {}", e));
-                        return;
-                    }
-                }
+                Self::normalize_id(&source, &mut self.old_id, "Old ")?;
+                Self::normalize_id(&source, &mut self.new_id, "New ")?;
                 if self.old_id == self.new_id {
-                    self.status = Status::Error("Old and New IDs cannot be the same".to_string());
-                    return;
+                    return Err("Old and New IDs cannot be the same".to_string());
+                }
+            },
+            Mode::Rename => {
+                Self::normalize_id(&source, &mut self.old_id, "Old ")?;
+                if !self.new_id.is_empty() {
+                    Self::normalize_id(&source, &mut self.new_id, "New ")?;
                 }
+                if self.rename_from.trim() == self.rename_to.trim() && self.old_id == self.get_rename_id() {
+                    return Err("Renaming to the same name and ID changes nothing".to_string());
+                }
+            },
+            Mode::Convert => {
+                let target = self.get_target_profile().cloned().ok_or("Pick a platform to convert to")?;
+                Self::normalize_id(&source, &mut self.old_id, "Old ")?;
+                Self::normalize_id(&target, &mut self.new_id, "New ")?;
             },
         }
+        Ok(())
+    }
 
-        let input = PathBuf::from(&self.input_dir);
-        let output = self.get_final_output_path();
-        let code = self.get_game_code();
-        
-        if self.mode == Mode::Encrypt {
-            if let Ok(files) = Self::collect_files(&input) {
-                if let Some(first_file) = files.first() {
-                    if let Ok(data) = fs::read(first_file) {
-                        if Self::is_file_encrypted(&data) {
-                            self.status = Status::EncryptionWarning(
-                                input.clone(),
-                                output,
-                                code.clone(),
-                                self.steam_id.clone()
-                            );
-                            return;
-                        }
-                    }
-                }
-            }
+    fn process_files(&mut self) {
+        if let Err(e) = self.validate_ids() {
+            self.status = Status::Error(e);
+            return;
+        }
+
+        // Verify is itself the check, so don't ask first
+        if self.mode == Mode::Verify {
+            self.start_processing();
+            return;
         }
 
-        self.start_processing();
+        let pending = PendingBatch {
+            input: PathBuf::from(&self.input_dir),
+            output: self.get_final_output_path(),
+            code: self.get_game_code(),
+            op: self.get_operation(),
+        };
+        let expected = self.get_expected_state();
+        let id = self.get_check_id().to_string();
+        let rules = self.get_game().files.clone();
+        self.spawn_work(move || {
+            match identify::classify_folder(&pending.input, &pending.code, Some(&id), &rules) {
+                Ok(states) if states.iter().any(|f| f.state != expected) => WorkResult::Review(Ok(states), Some(pending)),
+                _ => WorkResult::Ready(pending),
+            }
+        });
     }
 
-    fn start_processing(&mut self) {
+    fn check_files(&mut self) {
         let input = PathBuf::from(&self.input_dir);
-        let output = self.get_final_output_path();
         let code = self.get_game_code();
-        let mode = self.mode.clone();
-        
+        let id = match Some(self.get_check_id()).filter(|id| !id.is_empty()) {
+            Some(id) => match ids::normalize_id(self.get_platform_profile(), id) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    self.status = Status::Error(format!("Invalid {}: {}", self.get_platform_profile().id_format.label(), e));
+                    return;
+                }
+            },
+            None => None,
+        };
+        let rules = self.get_game().files.clone();
+        self.spawn_work(move || {
+            let states = identify::classify_folder(&input, &code, id.as_deref(), &rules).map_err(|e| e.to_string());
+            WorkResult::Review(states, None)
+        });
+    }
+
+    // Runs `work` on a worker thread, showing the spinner until it reports back
+    fn spawn_work(&mut self, work: impl FnOnce() -> WorkResult + Send + 'static) {
         let (tx, rx) = mpsc::channel();
         self.progress_rx = Some(rx);
         self.status = Status::Processing;
+        thread::spawn(move || {
+            tx.send(work()).ok();
+        });
+    }
 
-        match mode {
-            Mode::Decrypt => {
-                let id = self.steam_id.clone();
-                thread::spawn(move || {
-                    Self::process_decrypt(input, output, code, id, tx);
-                });
-            },
-            Mode::Encrypt => {
-                let id = self.steam_id.clone();
-                thread::spawn(move || {
-                    Self::process_encrypt(input, output, code, id, tx);
-                });
-            },
-            Mode::Resign => {
-                let old = self.old_id.clone();
-                let new = self.new_id.clone();
-                thread::spawn(move || {
-                    Self::process_resign(input, output, code, old, new, tx);
+    fn finish_work(&mut self, result: WorkResult) {
+        match result {
+            WorkResult::Batch(result) => {
+                self.status = match result {
+                    Ok(report) => Status::Completed(report),
+                    Err(err) => Status::Error(err),
+                };
+                self.load_history();
+            }
+            WorkResult::Review(Ok(states), pending) => self.status = Status::Review(states, pending),
+            WorkResult::Review(Err(err), _) => self.status = Status::Error(err),
+            WorkResult::Ready(pending) => self.spawn_batch(pending.op, pending.input, pending.output, pending.code),
+            WorkResult::Identified(result, candidates) => {
+                self.identifying = false;
+                self.status = Status::Idle;
+                self.identified = Some(match result {
+                    Ok(Some(owner)) => {
+                        self.steam_id = owner.id.clone();
+                        self.old_id = owner.id.clone();
+                        Ok(owner)
+                    }
+                    Ok(None) => Err(format!("None of the {} candidate IDs can decrypt these saves", candidates)),
+                    Err(e) => Err(e),
                 });
+            }
+            WorkResult::Detected(result, id) => self.status = match result {
+                Ok(Some(detection)) => self.use_detection(&detection),
+                Ok(None) => Status::Error(format!("No supported game decrypts these saves with {}", id)),
+                Err(e) => Status::Error(e),
             },
         }
     }
 
-    fn process_decrypt(input: PathBuf, output: PathBuf, code: String, id: String, tx: mpsc::Sender<String>) {
-        let result = (|| -> Result<String, Box<dyn std::error::Error>> {
-            if !input.exists() {
-                return Err("Input path does not exist".into());
-            }
+    fn get_batch_options(&self) -> Result<BatchOptions, String> {
+        let logical_names = self.logical_names
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(batch::parse_logical_name)
+            .collect::<Result<_, _>>()?;
+        Ok(BatchOptions {
+            continue_on_error: self.continue_on_error,
+            copy_skipped: self.copy_skipped,
+            rules: self.get_game().files.clone(),
+            logical_names,
+            conflicts: self.conflicts,
+            game: self.get_game().slug.clone(),
+            platform: Some(self.platform.clone()),
+            target_platform: (self.mode == Mode::Convert).then(|| self.target_platform.clone()),
+            redact_ids: self.redact_ids,
+        })
+    }
 
-            if input.is_file() {
-                return Err("Input path must be a directory, not a file".into());
-            }
+    fn preview(&mut self) {
+        let input = PathBuf::from(&self.input_dir);
+        let output = self.get_final_output_path();
+        let op = self.get_operation();
+        let plan = self.get_batch_options().and_then(|options| {
+            let plan = if self.in_place {
+                batch::plan_in_place(&op, &input, &options)
+            } else {
+                batch::plan(&op, &input, &output, &options)
+            };
+            plan.map_err(|e| e.to_string())
+        });
+        self.status = match plan {
+            Ok(plan) => Status::Preview(plan),
+            Err(e) => Status::Error(e.to_string()),
+        };
+    }
 
-            let files = Self::collect_files(&input)?;
-            fs::create_dir_all(&output).map_err(|e| format!("Failed to create output directory: {}", e))?;
-            
-            if files.is_empty() {
-                return Err("No files found in input directory".into());
-            }
-
-            let mut processed = 0;
-            let mut log = String::new();
-
-            for file in files {
-                let name = file.file_name()
-                    .ok_or("Invalid file name")?
-                    .to_str()
-                    .ok_or("Invalid file name encoding")?;
-                
-                log.push_str(&format!("Decrypting {}...\n", name));
-                
-                let data = fs::read(&file).map_err(|e| format!("Failed to read file {}: {}", name, e))?;
-                let decrypted = IdCrypto::decrypt_file(&data, name, &code, &id)
-                    .map_err(|_| format!("Failed to decrypt {}: Check if SteamID is correct", name))?;
-                
-                let rel = file.strip_prefix(&input)?;
-                let out = output.join(rel);
-                if let Some(parent) = out.parent() {
-                    fs::create_dir_all(parent)?;
-                }
-                fs::write(&out, decrypted)
-                    .map_err(|e| format!("Failed to write decrypted file {}: {}", name, e))?;
-                
-                processed += 1;
-            }
+    fn get_expected_state(&self) -> EncryptionState {
+        match self.mode {
+            Mode::Encrypt => EncryptionState::Plaintext,
+            Mode::Decrypt | Mode::Resign | Mode::Verify | Mode::Rename | Mode::Convert => EncryptionState::EncryptedForKey,
+        }
+    }
 
-            let ts: DateTime<Local> = Local::now();
-            let mut info = format!("Processing completed at: {}\n\n", ts.format("%Y-%m-%d %H:%M:%S"));
-            info.push_str(&format!("Decrypted {} files from SteamID {}\n\n", processed, id));
-            info.push_str(&log);
-            
-            fs::write(output.join("INFO.txt"), info)?;
-            Ok(format!("Successfully decrypted {} files", processed))
-        })();
+    // The ID the input files should already be encrypted with, or will be for Encrypt
+    fn get_check_id(&self) -> &str {
+        match self.mode {
+            Mode::Decrypt | Mode::Encrypt | Mode::Verify => &self.steam_id,
+            Mode::Resign | Mode::Rename | Mode::Convert => &self.old_id,
+        }
+    }
 
-        match result {
-            Ok(msg) => tx.send(format!("COMPLETED: {}", msg)).ok(),
-            Err(e) => tx.send(format!("ERROR: {}", e)).ok(),
+    fn start_processing(&mut self) {
+        let input = PathBuf::from(&self.input_dir);
+        let output = self.get_final_output_path();
+        let code = self.get_game_code();
+        self.spawn_batch(self.get_operation(), input, output, code);
+    }
+
+    fn spawn_batch(&mut self, op: Operation, input: PathBuf, output: PathBuf, code: String) {
+        let options = match self.get_batch_options() {
+            Ok(options) => options,
+            Err(e) => {
+                self.status = Status::Error(e);
+                return;
+            }
         };
+        let in_place = self.in_place;
+        let history_file = self.history_file.clone();
+        self.spawn_work(move || {
+            let result = if op.writes_output() {
+                history::run(&op, &input, &output, &code, &options, in_place).map(|(mut report, run)| {
+                    if let Err(e) = History::append(&history_file, run) {
+                        report.message = format!("{} (not recorded in the run history: {})", report.message, e);
+                    }
+                    report
+                })
+            } else {
+                batch::process(&op, &input, &output, &code, &options)
+            };
+            WorkResult::Batch(result.map_err(|e| e.to_string()))
+        });
     }
 
-    fn process_encrypt(input: PathBuf, output: PathBuf, code: String, id: String, tx: mpsc::Sender<String>) {
-        let result = (|| -> Result<String, Box<dyn std::error::Error>> {
-            if !input.exists() {
-                return Err("Input path does not exist".into());
+    // Shows what a Steam ID field resolves to in every form it's commonly
+    // shared in, and offers the IDs the release is known to use
+    fn id_hint(ui: &mut egui::Ui, field: &mut String, profile: Option<&PlatformProfile>, accounts: &[SteamAccount]) {
+        let Some(profile) = profile else {
+            return;
+        };
+        if profile.id_format == IdFormat::Steam64 && !accounts.is_empty() {
+            ui.menu_button("👤 Steam accounts on this PC", |ui| {
+                for account in accounts {
+                    if ui.selectable_label(account.steam_id == *field, account.label()).clicked() {
+                        field.clone_from(&account.steam_id);
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+        if profile.id_format == IdFormat::Steam64 && !field.trim().is_empty() {
+            let hint = match SteamId::parse(field) {
+                Ok(id) => egui::RichText::new(format!("= {} · {} · {}", id, id.steam2(), id.steam3()))
+                    .color(egui::Color32::from_rgb(100, 150, 255)),
+                Err(e) => egui::RichText::new(e).color(egui::Color32::YELLOW),
+            };
+            ui.label(hint.size(10.0));
+        } else if !field.trim().is_empty()
+            && let Err(e) = ids::normalize_id(profile, field) {
+            ui.label(egui::RichText::new(e).size(10.0).color(egui::Color32::YELLOW));
+        }
+
+        for known in &profile.known_ids {
+            if known.id == *field {
+                continue;
             }
+            ui.horizontal(|ui| {
+                if ui.small_button(format!("Use {}", known.id)).clicked() {
+                    field.clone_from(&known.id);
+                }
+                ui.label(egui::RichText::new(&known.note).size(10.0));
+            });
+        }
+    }
 
-            if input.is_file() {
-                return Err("Input path must be a directory, not a file".into());
+    fn file_states(ui: &mut egui::Ui, states: &[FileState], expected: EncryptionState) {
+        egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
+            for file in states {
+                let color = if file.state == expected {
+                    egui::Color32::GREEN
+                } else {
+                    egui::Color32::YELLOW
+                };
+                ui.colored_label(color, format!("{}: {}", file.path.display(), file.state.describe()));
             }
+        });
+    }
 
-            let files = Self::collect_files(&input)?;
-            fs::create_dir_all(&output).map_err(|e| format!("Failed to create output directory: {}", e))?;
-            
-            if files.is_empty() {
-                return Err("No files found in input directory".into());
-            }
-
-            let mut processed = 0;
-            let mut log = String::new();
-
-            for file in files {
-                let name = file.file_name()
-                    .ok_or("Invalid file name")?
-                    .to_str()
-                    .ok_or("Invalid file name encoding")?;
-                
-                log.push_str(&format!("Encrypting {}...\n", name));
-                
-                let data = fs::read(&file).map_err(|e| format!("Failed to read file {}: {}", name, e))?;
-                let encrypted = IdCrypto::encrypt_file(&data, name, &code, &id)
-                    .map_err(|_| format!("Failed to encrypt {}: Check if SteamID is correct", name))?;
-                
-                let rel = file.strip_prefix(&input)?;
-                let out = output.join(rel);
-                if let Some(parent) = out.parent() {
-                    fs::create_dir_all(parent)?;
+    fn plan_list(ui: &mut egui::Ui, plan: &Plan) {
+        egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
+            for file in plan.files.iter().chain(&plan.copied) {
+                if file.exists {
+                    ui.colored_label(egui::Color32::YELLOW, plan.describe_file(file));
+                } else {
+                    ui.label(plan.describe_file(file));
                 }
-                fs::write(&out, encrypted)
-                    .map_err(|e| format!("Failed to write encrypted file {}: {}", name, e))?;
-                
-                processed += 1;
             }
+            for file in &plan.skipped {
+                let source = file.strip_prefix(&plan.input).unwrap_or(file);
+                ui.colored_label(egui::Color32::GRAY, format!("{}: {}", source.display(), FileStatus::Skipped.describe()));
+            }
+        });
+    }
 
-            let ts: DateTime<Local> = Local::now();
-            let mut info = format!("Processing completed at: {}\n\n", ts.format("%Y-%m-%d %H:%M:%S"));
-            info.push_str(&format!("Encrypted {} files for SteamID {}\n\n", processed, id));
-            info.push_str(&log);
-            
-            fs::write(output.join("INFO.txt"), info)?;
-            Ok(format!("Successfully encrypted {} files", processed))
-        })();
-
-        match result {
-            Ok(msg) => tx.send(format!("COMPLETED: {}", msg)).ok(),
-            Err(e) => tx.send(format!("ERROR: {}", e)).ok(),
-        };
+    fn file_results(ui: &mut egui::Ui, report: &BatchReport) {
+        ui.collapsing(format!("File results ({})", report.files.len()), |ui| {
+            egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                for file in &report.files {
+                    let color = match file.status {
+                        FileStatus::Ok if file.key_name.is_some() => egui::Color32::YELLOW,
+                        FileStatus::Ok => egui::Color32::GREEN,
+                        FileStatus::Skipped | FileStatus::Copied | FileStatus::Exists => egui::Color32::GRAY,
+                        _ => egui::Color32::RED,
+                    };
+                    ui.colored_label(color, format!("{}: {}", file.path.display(), file.describe()));
+                }
+            });
+        });
     }
 
-    fn process_resign(input: PathBuf, output: PathBuf, code: String, old: String, new: String, tx: mpsc::Sender<String>) {
-        let result = (|| -> Result<String, Box<dyn std::error::Error>> {
-            if !input.exists() {
-                return Err("Input path does not exist".into());
+    fn browse_folder(&mut self, for_output: bool) {
+        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+            let s = path.to_string_lossy().to_string();
+            if for_output {
+                self.output_dir = s;
+            } else {
+                self.input_dir = s;
             }
+        }
+    }
 
-            if input.is_file() {
-                return Err("Input path must be a directory, not a file".into());
+    fn load_candidates_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            match identify::read_candidates(&path) {
+                Ok(ids) => {
+                    if !self.candidates.is_empty() && !self.candidates.ends_with('\n') {
+                        self.candidates.push('\n');
+                    }
+                    self.candidates.push_str(&ids.join("\n"));
+                }
+                Err(e) => self.identified = Some(Err(format!("Failed to read {}: {}", path.display(), e))),
             }
+        }
+    }
 
-            let files = Self::collect_files(&input)?;
-            fs::create_dir_all(&output).map_err(|e| format!("Failed to create output directory: {}", e))?;
-            
-            if files.is_empty() {
-                return Err("No files found in input directory".into());
-            }
-
-            let mut processed = 0;
-            let mut log = String::new();
-
-            for file in files {
-                let name = file.file_name()
-                    .ok_or("Invalid file name")?
-                    .to_str()
-                    .ok_or("Invalid file name encoding")?;
-                
-                log.push_str(&format!("Resigning {}...\n", name));
-                
-                let data = fs::read(&file).map_err(|e| format!("Failed to read file {}: {}", name, e))?;
-                let resigned = IdCrypto::resign_file(&data, name, &code, &old, &new)
-                    .map_err(|_| format!("Failed to resign {}: Check if Old SteamID is correct", name))?;
-                
-                let rel = file.strip_prefix(&input)?;
-                let out = output.join(rel);
-                if let Some(parent) = out.parent() {
-                    fs::create_dir_all(parent)?;
+    fn run_identify(&mut self) {
+        let mut candidates = identify::parse_candidates(&self.candidates);
+        if self.get_platform_profile().id_format == IdFormat::Steam64 {
+            for id in candidates.iter_mut() {
+                if let Ok(steam_id) = SteamId::parse(id) {
+                    *id = steam_id.to_string();
+                }
+            }
+        }
+        if self.use_local_steam && self.platform == Platform::Steam {
+            for id in local_steam_ids() {
+                if !candidates.contains(&id) {
+                    candidates.push(id);
                 }
-                fs::write(&out, resigned)
-                    .map_err(|e| format!("Failed to write resigned file {}: {}", name, e))?;
-                
-                processed += 1;
             }
+        }
 
-            let ts: DateTime<Local> = Local::now();
-            let mut info = format!("Processing completed at: {}\n\n", ts.format("%Y-%m-%d %H:%M:%S"));
-            info.push_str(&format!("Resigned {} files from SteamID {} to SteamID {}\n\n", processed, old, new));
-            info.push_str(&log);
-            
-            fs::write(output.join("INFO.txt"), info)?;
-            Ok(format!("Successfully resigned {} files", processed))
-        })();
+        let input = PathBuf::from(&self.input_dir);
+        let code = self.get_game_code();
+        let rules = self.get_game().files.clone();
+        self.identified = None;
+        self.identifying = true;
+        self.spawn_work(move || {
+            let owner = identify::identify_owner(&input, &code, &candidates, &rules).map_err(|e| e.to_string());
+            WorkResult::Identified(owner, candidates.len())
+        });
+    }
 
-        match result {
-            Ok(msg) => tx.send(format!("COMPLETED: {}", msg)).ok(),
-            Err(e) => tx.send(format!("ERROR: {}", e)).ok(),
+    fn find_save_folders(&mut self) {
+        let slots = locate::find_save_slots(self.get_game());
+        self.status = if slots.is_empty() {
+            Status::Info(format!("No save folders found for {}", self.get_game().name))
+        } else {
+            Status::Slots(slots)
         };
     }
 
-    fn is_save(path: &PathBuf) -> bool {
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            let lower = name.to_lowercase();
-            return lower.ends_with(".bin") || 
-                lower.ends_with(".dat") || 
-                lower.ends_with(".details") ||
-                lower.ends_with(".details-backup") ||
-                lower.ends_with(".dat-backup");
+    fn use_save_slot(&mut self, slot: &SaveSlot) -> Status {
+        self.input_dir = slot.path.display().to_string();
+        self.platform = slot.platform.clone();
+        match &slot.owner {
+            Some(owner) => {
+                self.steam_id = owner.clone();
+                self.old_id = owner.clone();
+                Status::Info(format!("Using saves of {}", owner))
+            }
+            None => Status::Info("Owner unknown, use Identify to find the ID these saves are signed with".to_string()),
         }
-        false
     }
 
-    fn collect_files(path: &PathBuf) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
-        let mut files = Vec::new();
-        let mut bad = Vec::new();
-        
-        if path.is_file() {
-            if Self::is_save(path) {
-                files.push(path.clone());
-            } else {
-                bad.push(path.clone());
-            }
-        } else if path.is_dir() {
-            for entry in fs::read_dir(path)? {
-                let entry = entry?;
-                let p = entry.path();
-                if p.is_file() {
-                    if Self::is_save(&p) {
-                        files.push(p);
-                    } else {
-                        bad.push(p);
-                    }
-                } else if p.is_dir() {
-                    let (sub, sub_bad) = Self::walk_dir(&p)?;
-                    files.extend(sub);
-                    bad.extend(sub_bad);
-                }
-            }
-        }
-        
-        if !bad.is_empty() {
-            let _names: Vec<String> = bad
-                .iter()
-                .filter_map(|p| p.file_name().and_then(|n| n.to_str().map(|s| s.to_string())))
-                .collect();
-        }
-        
-        if files.is_empty() {
-            return Err("No supported save files (.bin / .dat / .details / .details-backup / .dat-backup) found in the directory".into());
+    // The ID the existing saves are signed with, empty for Encrypt since the input is plaintext
+    fn get_known_id(&self) -> &str {
+        match self.mode {
+            Mode::Decrypt | Mode::Verify => &self.steam_id,
+            Mode::Resign | Mode::Rename | Mode::Convert => &self.old_id,
+            Mode::Encrypt => "",
         }
-        
-        Ok(files)
     }
 
-    fn walk_dir(path: &PathBuf) -> Result<(Vec<PathBuf>, Vec<PathBuf>), Box<dyn std::error::Error>> {
-        let mut files = Vec::new();
-        let mut bad = Vec::new();
-        
-        if path.is_file() {
-            if Self::is_save(path) {
-                files.push(path.clone());
-            } else {
-                bad.push(path.clone());
-            }
-        } else if path.is_dir() {
-            for entry in fs::read_dir(path)? {
-                let entry = entry?;
-                let p = entry.path();
-                if p.is_file() {
-                    if Self::is_save(&p) {
-                        files.push(p);
-                    } else {
-                        bad.push(p);
-                    }
-                } else if p.is_dir() {
-                    let (sub, sub_bad) = Self::walk_dir(&p)?;
-                    files.extend(sub);
-                    bad.extend(sub_bad);
-                }
-            }
-        }
-        
-        Ok((files, bad))
+    fn detect_game(&mut self, id: &str) {
+        let input = PathBuf::from(&self.input_dir);
+        let id = id.to_string();
+        let registry = self.registry.clone();
+        self.spawn_work(move || {
+            let detection = identify::detect_game(&input, &id, &registry).map_err(|e| e.to_string());
+            WorkResult::Detected(detection, id)
+        });
     }
 
-    fn browse_folder(&mut self, for_output: bool) {
-        if let Some(path) = rfd::FileDialog::new().pick_folder() {
-            let s = path.to_string_lossy().to_string();
-            if for_output {
-                self.output_dir = s;
-            } else {
-                self.input_dir = s;
-            }
+    // Keeps the selected game and platform if they match, otherwise picks the first match
+    fn use_detection(&mut self, detection: &Detection) -> Status {
+        let current = detection.matches.iter()
+            .find(|m| m.slug == self.get_game().slug && m.platform == self.platform);
+        let chosen = current.unwrap_or(&detection.matches[0]).clone();
+        if let Some(idx) = self.registry.games().iter().position(|g| g.slug == chosen.slug) {
+            self.game_idx = idx;
         }
+        self.platform = chosen.platform;
+
+        let names: Vec<String> = detection.matches.iter()
+            .filter_map(|m| self.registry.get(&m.slug).map(|g| format!("{} ({})", g.name, m.platform.label())))
+            .collect();
+        Status::Info(format!("Detected: {}", names.join(", ")))
     }
 
     fn path_input_row(ui: &mut egui::Ui, label: &str, path: &mut String) -> bool {
@@ -585,12 +671,9 @@ impl SaveDataApp {
         });
         
         ui.ctx().input(|i| {
-            if !i.raw.dropped_files.is_empty() {
-                if let Some(f) = i.raw.dropped_files.first() {
-                    if let Some(p) = &f.path {
-                        *path = p.to_string_lossy().to_string();
-                    }
-                }
+            if let Some(f) = i.raw.dropped_files.first()
+                && let Some(p) = &f.path {
+                *path = p.to_string_lossy().to_string();
             }
         });
         
@@ -603,25 +686,60 @@ impl SaveDataApp {
             ui.radio_value(&mut self.mode, Mode::Resign, "Resign");
             ui.radio_value(&mut self.mode, Mode::Decrypt, "Decrypt");
             ui.radio_value(&mut self.mode, Mode::Encrypt, "Encrypt");
+            ui.radio_value(&mut self.mode, Mode::Verify, "Verify");
+            ui.radio_value(&mut self.mode, Mode::Rename, "Rename");
+            ui.radio_value(&mut self.mode, Mode::Convert, "Convert");
         });
 
         ui.separator();
+        let detect_id = self.get_known_id().to_string();
+        let busy = self.progress_rx.is_some();
+        let mut detect = false;
         ui.horizontal(|ui| {
             ui.label("Game:");
             egui::ComboBox::from_label("")
-                .selected_text(GAMES[self.game_idx].name)
+                .selected_text(&self.registry.games()[self.game_idx].name)
                 .show_ui(ui, |ui| {
-                    for (i, game) in GAMES.iter().enumerate() {
-                        ui.selectable_value(&mut self.game_idx, i, game.name);
+                    for (i, game) in self.registry.games().iter().enumerate() {
+                        ui.selectable_value(&mut self.game_idx, i, &game.name);
                     }
                 });
+            ui.add_enabled_ui(!self.input_dir.is_empty() && !detect_id.is_empty() && !busy, |ui| {
+                detect = ui.button("🔎 Detect")
+                    .on_hover_text("Find the game and platform by trying every game code with the entered ID")
+                    .clicked();
+            });
         });
+        if detect {
+            self.detect_game(&detect_id);
+        }
 
-        if GAMES[self.game_idx].platforms.len() > 1 {
+        let game = &self.registry.games()[self.game_idx];
+        if !game.supports(&self.platform) {
+            self.platform = game.platforms[0].platform.clone();
+        }
+        if game.platforms.len() > 1 {
             ui.horizontal(|ui| {
                 ui.label("Platform:");
-                ui.radio_value(&mut self.platform, Platform::Steam, "Steam");
-                ui.radio_value(&mut self.platform, Platform::GOG, "GOG");
+                for profile in &game.platforms {
+                    ui.radio_value(&mut self.platform, profile.platform.clone(), profile.platform.label());
+                }
+            });
+        }
+        if self.mode == Mode::Convert {
+            if let Some(other) = game.platforms.iter().find(|p| p.platform != self.platform)
+                && (self.target_platform == self.platform || !game.supports(&self.target_platform)) {
+                self.target_platform = other.platform.clone();
+            }
+            ui.horizontal(|ui| {
+                ui.label("Convert to:");
+                let targets: Vec<_> = game.platforms.iter().filter(|p| p.platform != self.platform).collect();
+                if targets.is_empty() {
+                    ui.label(format!("{} is only available on {}", game.name, self.platform.label()));
+                }
+                for profile in targets {
+                    ui.radio_value(&mut self.target_platform, profile.platform.clone(), profile.platform.label());
+                }
             });
         }
 
@@ -629,61 +747,137 @@ impl SaveDataApp {
         if Self::path_input_row(ui, "Input Folder:", &mut self.input_dir) {
             self.browse_folder(false);
         }
+        if ui.button("📂 Find save folders").on_hover_text("Look for this game's saves in Steam, Proton and Saved Games folders").clicked() {
+            self.find_save_folders();
+        }
+        if self.mode != Mode::Verify {
+            ui.checkbox(&mut self.in_place, "Update the input folder in place")
+                .on_hover_text("Backs up every file to a timestamped folder next to the input folder first");
+        }
         ui.add_space(5.0);
-        if !self.input_dir.is_empty() {
-            let out = self.get_final_output_path();
-            let display = format!("→ Files will be saved to: {}", out.display());
+        if !self.input_dir.is_empty() && self.mode != Mode::Verify {
+            let display = if self.in_place {
+                let backup = batch::output_dir(&PathBuf::from(&self.input_dir), None, "_backup_<date>-<time>");
+                format!("→ Files will be replaced in place, originals backed up to: {}", backup.display())
+            } else {
+                format!("→ Files will be saved to: {}", self.conflicts.output_dir(&self.get_final_output_path()).display())
+            };
             ui.label(egui::RichText::new(display).size(10.0).color(egui::Color32::from_rgb(100, 150, 255)));
         }
 
         ui.separator();
 
+        let source = self.get_platform_profile().clone();
+        let target = self.get_target_profile().cloned();
         match self.mode {
-            Mode::Decrypt | Mode::Encrypt => {
+            Mode::Decrypt | Mode::Encrypt | Mode::Verify => {
                 ui.horizontal(|ui| {
                     ui.label(format!("{}:", self.get_id_label()));
                     ui.text_edit_singleline(&mut self.steam_id);
                 });
+                Self::id_hint(ui, &mut self.steam_id, Some(&source), &self.steam_accounts);
             }
             Mode::Resign => {
                 ui.horizontal(|ui| {
                     ui.label(format!("Old {}:", self.get_id_label()));
                     ui.text_edit_singleline(&mut self.old_id);
                 });
+                Self::id_hint(ui, &mut self.old_id, Some(&source), &self.steam_accounts);
+                ui.horizontal(|ui| {
+                    ui.label(format!("New {}:", self.get_id_label()));
+                    ui.text_edit_singleline(&mut self.new_id);
+                });
+                Self::id_hint(ui, &mut self.new_id, Some(&source), &self.steam_accounts);
+            }
+            Mode::Rename => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Old {}:", self.get_id_label()));
+                    ui.text_edit_singleline(&mut self.old_id);
+                });
+                Self::id_hint(ui, &mut self.old_id, Some(&source), &self.steam_accounts);
                 ui.horizontal(|ui| {
                     ui.label(format!("New {}:", self.get_id_label()));
+                    ui.add(egui::TextEdit::singleline(&mut self.new_id).hint_text("same as old"));
+                });
+                Self::id_hint(ui, &mut self.new_id, Some(&source), &self.steam_accounts);
+                ui.horizontal(|ui| {
+                    ui.label("Rename:");
+                    ui.add(egui::TextEdit::singleline(&mut self.rename_from).hint_text("game").desired_width(120.0));
+                    ui.label("→");
+                    ui.add(egui::TextEdit::singleline(&mut self.rename_to).hint_text("game2").desired_width(120.0));
+                });
+                ui.label(egui::RichText::new("Files named game or game.* are re-encrypted under the new name").size(10.0));
+            }
+            Mode::Convert => {
+                let new_label = self.get_target_profile().map_or("User ID", |p| p.id_format.label());
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} {}:", self.platform.label(), self.get_id_label()));
+                    ui.text_edit_singleline(&mut self.old_id);
+                });
+                Self::id_hint(ui, &mut self.old_id, Some(&source), &self.steam_accounts);
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} {}:", self.target_platform.label(), new_label));
                     ui.text_edit_singleline(&mut self.new_id);
                 });
+                Self::id_hint(ui, &mut self.new_id, target.as_ref(), &self.steam_accounts);
             }
         }
 
+        ui.collapsing("Logical file names", |ui| {
+            ui.label(egui::RichText::new(
+                "Renamed or backup copies are matched to their original name automatically. \
+                 To set it yourself, enter one file per line as file = name, e.g. game.details-backup = game.details"
+            ).size(10.0));
+            ui.add(egui::TextEdit::multiline(&mut self.logical_names).desired_rows(2).desired_width(f32::INFINITY));
+        });
+
         ui.separator();
 
         let can_process = match self.mode {
-            Mode::Decrypt | Mode::Encrypt => {
+            Mode::Decrypt | Mode::Encrypt | Mode::Verify => {
                 !self.input_dir.is_empty() && !self.steam_id.is_empty()
             }
             Mode::Resign => {
                 !self.input_dir.is_empty() && 
                 !self.old_id.is_empty() && !self.new_id.is_empty()
             }
+            Mode::Rename => {
+                !self.input_dir.is_empty() && !self.old_id.is_empty() &&
+                !self.rename_from.trim().is_empty() && !self.rename_to.trim().is_empty()
+            }
+            Mode::Convert => {
+                !self.input_dir.is_empty() && !self.old_id.is_empty() &&
+                !self.new_id.is_empty() && self.get_target_profile().is_some()
+            }
         };
-        let processing = matches!(self.status, Status::Processing);
+        let processing = self.progress_rx.is_some();
         
         ui.add_enabled_ui(can_process && !processing, |ui| {
             let btn_text = match self.mode {
                 Mode::Decrypt => "🔓 Decrypt Files",
                 Mode::Encrypt => "🔒 Encrypt Files",
                 Mode::Resign => "✍ Resign Files",
+                Mode::Verify => "✔ Verify Files",
+                Mode::Rename => "✏ Rename Files",
+                Mode::Convert => "🔁 Convert Files",
             };
             
-            if ui.button(btn_text).clicked() {
-                self.process_files();
-            }
+            ui.horizontal(|ui| {
+                if ui.button(btn_text).clicked() {
+                    self.process_files();
+                }
+                if ui.button("🔍 Check Files").clicked() {
+                    self.check_files();
+                }
+                if ui.button("👁 Preview").clicked() {
+                    self.preview();
+                }
+            });
         });
 
         let mut new_status = None;
-        let mut start_encrypt = None;
+        let mut start_batch = None;
+        let expected = self.get_expected_state();
         
         match &self.status {
             Status::Idle => {},
@@ -694,21 +888,72 @@ impl SaveDataApp {
                     ui.label("Processing...");
                 });
             }
-            Status::Completed(msg) => {
+            Status::Completed(report) => {
                 ui.separator();
-                ui.colored_label(egui::Color32::GREEN, format!("✅ {}", msg));
+                if report.is_success() {
+                    ui.colored_label(egui::Color32::GREEN, format!("✅ {}", report.message));
+                } else {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠️ {}", report.message));
+                }
+                Self::file_results(ui, report);
             }
             Status::Error(msg) => {
                 ui.separator();
                 ui.colored_label(egui::Color32::RED, format!("❌ {}", msg));
             }
-            Status::EncryptionWarning(input, output, code, id) => {
+            Status::Info(msg) => {
+                ui.separator();
+                ui.colored_label(egui::Color32::from_rgb(100, 150, 255), format!("ℹ {}", msg));
+            }
+            Status::Preview(plan) => {
+                ui.separator();
+                ui.label(plan.summary());
+                Self::plan_list(ui, plan);
+                if ui.button("Close").clicked() {
+                    new_status = Some(Status::Idle);
+                }
+            }
+            Status::Slots(slots) => {
                 ui.separator();
-                ui.colored_label(egui::Color32::YELLOW, "⚠️ Warning: Files appear to be already encrypted!");
-                ui.label("Are you sure you want to encrypt already encrypted files?");
+                ui.label(format!("Found {} save folders:", slots.len()));
+                let mut chosen = None;
+                egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                    for slot in slots {
+                        ui.horizontal(|ui| {
+                            if ui.button("Use").clicked() {
+                                chosen = Some(slot.clone());
+                            }
+                            ui.label(slot.describe());
+                        });
+                    }
+                });
+                if let Some(slot) = chosen {
+                    new_status = Some(self.use_save_slot(&slot));
+                }
+                if ui.button("Close").clicked() {
+                    new_status = Some(Status::Idle);
+                }
+            }
+            Status::Review(states, None) => {
+                ui.separator();
+                Self::file_states(ui, states, expected);
+                if ui.button("Close").clicked() {
+                    new_status = Some(Status::Idle);
+                }
+            }
+            Status::Review(states, Some(pending)) => {
+                ui.separator();
+                if self.mode == Mode::Encrypt {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠️ Warning: Some files appear to be already encrypted!");
+                    ui.label("Are you sure you want to encrypt already encrypted files?");
+                } else {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠️ Warning: Some files are not encrypted for this {}!", self.get_id_label()));
+                    ui.label("These files will fail to process. Continue anyway?");
+                }
+                Self::file_states(ui, states, expected);
                 ui.horizontal(|ui| {
                     if ui.button("Yes, Continue").clicked() {
-                        start_encrypt = Some((input.clone(), output.clone(), code.clone(), id.clone()));
+                        start_batch = Some(pending.clone());
                         new_status = Some(Status::Processing);
                     }
                     if ui.button("Cancel").clicked() {
@@ -722,13 +967,8 @@ impl SaveDataApp {
             self.status = status;
         }
         
-        if let Some((input, output, code, id)) = start_encrypt {
-            let (tx, rx) = mpsc::channel();
-            self.progress_rx = Some(rx);
-            
-            thread::spawn(move || {
-                Self::process_encrypt(input, output, code, id, tx);
-            });
+        if let Some(pending) = start_batch {
+            self.spawn_batch(pending.op, pending.input, pending.output, pending.code);
         }
 
         ui.separator();
@@ -736,6 +976,7 @@ impl SaveDataApp {
             ui.label("• Resign: Transfer save files from a SteamID to another");
             ui.label("• Decrypt: Convert encrypted save files to readable format");
             ui.label("• Encrypt: Convert readable files back to encrypted format");
+            ui.label("• Verify: Check that every file decrypts with the ID, without writing anything");
             ui.horizontal(|ui| {
                 ui.label("• SteamID: Your 64-bit Steam ID");
                 ui.hyperlink_to("Click me to check", "https://steamdb.info/calculator/");
@@ -746,6 +987,104 @@ impl SaveDataApp {
         });
     }
 
+    fn identify_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Identify Owner");
+        ui.separator();
+
+        ui.label(format!(
+            "Finds which {} signed the saves in the input folder, using the game and platform selected on the Main tab.",
+            self.get_id_label()
+        ));
+        ui.add_space(5.0);
+
+        if Self::path_input_row(ui, "Input Folder:", &mut self.input_dir) {
+            self.browse_folder(false);
+        }
+
+        ui.label("Candidate IDs (one per line):");
+        egui::ScrollArea::vertical().max_height(80.0).show(ui, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut self.candidates).desired_rows(4).desired_width(f32::INFINITY));
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Load from file").clicked() {
+                self.load_candidates_file();
+            }
+            if self.platform == Platform::Steam {
+                ui.checkbox(&mut self.use_local_steam, "Include Steam accounts on this PC");
+            }
+        });
+
+        ui.separator();
+        ui.add_enabled_ui(!self.input_dir.is_empty() && self.progress_rx.is_none(), |ui| {
+            if ui.button("🔍 Identify").clicked() {
+                self.run_identify();
+            }
+        });
+
+        match &self.identified {
+            None if self.identifying => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Trying every candidate...");
+                });
+            }
+            None => {},
+            Some(Ok(owner)) => {
+                ui.colored_label(egui::Color32::GREEN, format!("✅ Owner: {}", owner.id));
+                ui.label(format!("Verified against {}", owner.file.display()));
+                ui.label("The ID has been filled in on the Main tab.");
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(egui::Color32::RED, format!("❌ {}", msg));
+            }
+        }
+    }
+
+    fn history_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Run History");
+        ui.separator();
+
+        ui.label("Every run that wrote files is listed here, newest first. Restore puts back the exact files a run replaced and removes the ones it created.");
+        ui.add_space(5.0);
+
+        ui.checkbox(&mut self.force_restore, "Restore even if files changed since the run")
+            .on_hover_text("Without this, a run is only restored while every file still holds what the run wrote");
+        ui.add_space(5.0);
+
+        let processing = self.progress_rx.is_some();
+        let mut restore = None;
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for run in self.history.iter().rev() {
+                ui.horizontal(|ui| {
+                    let enabled = !processing && run.restored.is_none();
+                    if ui.add_enabled(enabled, egui::Button::new("↩ Restore")).clicked() {
+                        restore = Some(run.clone());
+                    }
+                    ui.label(run.describe());
+                });
+            }
+        });
+        if self.history.is_empty() {
+            ui.label("No runs recorded yet");
+        }
+        if let Some(run) = restore {
+            self.restore_run(&run);
+        }
+
+        match &self.history_status {
+            Some(Ok(msg)) => {
+                ui.separator();
+                ui.colored_label(egui::Color32::GREEN, format!("✅ {}", msg));
+            }
+            Some(Err(msg)) => {
+                ui.separator();
+                ui.colored_label(egui::Color32::RED, format!("❌ {}", msg));
+            }
+            None => {}
+        }
+    }
+
     fn settings_tab(&mut self, ui: &mut egui::Ui) {
         ui.heading("Output Settings");
         ui.separator();
@@ -773,39 +1112,72 @@ impl SaveDataApp {
             }
             ui.label("(Will use input folder's parent directory)");
         });
+
+        ui.add_space(20.0);
+        ui.heading("Processing");
+        ui.separator();
+
+        if ui.checkbox(&mut self.continue_on_error, "Continue when a file fails")
+            .on_hover_text("The files that succeed are still written, the failures are listed")
+            .changed() {
+            self.save_config();
+        }
+        ui.label("• Failed files are listed in the results and in INFO.txt instead of stopping the batch");
+
+        if ui.checkbox(&mut self.copy_skipped, "Copy unrecognised files to the output folder").changed() {
+            self.save_config();
+        }
+        ui.label("• Screenshots, metadata and other unrecognised files are copied unchanged, so the output folder can replace the input folder");
+
+        ui.add_space(10.0);
+        ui.label("When the output folder already has a file:");
+        ui.horizontal(|ui| {
+            for policy in ConflictPolicy::ALL {
+                if ui.radio_value(&mut self.conflicts, policy, policy.label()).changed() {
+                    self.save_config();
+                }
+            }
+        });
+        ui.label("• Write to a new folder uses GAME-AUTOSAVE1_resigned (2) when GAME-AUTOSAVE1_resigned exists; Preview lists every conflict before processing");
+
+        ui.add_space(10.0);
+        if ui.checkbox(&mut self.redact_ids, "Redact IDs in INFO.json").changed() {
+            self.save_config();
+        }
+        ui.label("• INFO.json records every run for other tools; only the last four characters of each ID are kept");
     }
 }
 
 impl eframe::App for SaveDataApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Some(rx) = &self.progress_rx {
-            if let Ok(msg) = rx.try_recv() {
-                if msg.starts_with("COMPLETED:") {
-                    self.status = Status::Completed(msg[10..].to_string());
-                    self.progress_rx = None;
-                } else if msg.starts_with("ERROR:") {
-                    self.status = Status::Error(msg[6..].to_string());
-                    self.progress_rx = None;
-                }
-            }
+        if let Some(rx) = &self.progress_rx
+            && let Ok(result) = rx.try_recv() {
+            self.progress_rx = None;
+            self.finish_work(result);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut self.active_tab, Tab::Main, "Main");
+                ui.selectable_value(&mut self.active_tab, Tab::Identify, "Identify Owner");
+                ui.selectable_value(&mut self.active_tab, Tab::History, "History");
                 ui.selectable_value(&mut self.active_tab, Tab::Settings, "Settings");
             });
             
             ui.separator();
 
-            match self.active_tab {
-                Tab::Main => self.main_tab(ui),
-                Tab::Settings => self.settings_tab(ui),
-            }
+            // The tabs outgrow the window once reports and lists fill in
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                match self.active_tab {
+                    Tab::Main => self.main_tab(ui),
+                    Tab::Identify => self.identify_tab(ui),
+                    Tab::History => self.history_tab(ui),
+                    Tab::Settings => self.settings_tab(ui),
+                }
+            });
         });
 
-        let processing = matches!(self.status, Status::Processing);
-        if processing {
+        if self.progress_rx.is_some() {
             ctx.request_repaint();
         }
     }
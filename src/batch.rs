@@ -0,0 +1,977 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::games::{FileKind, FileRules, Platform};
+use crate::manifest::{Manifest, MANIFEST_FILE};
+use crate::logic::{CryptoError, IdCrypto};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Decrypt { id: String },
+    Encrypt { id: String },
+    Resign { old_id: String, new_id: String },
+    Verify { id: String },
+    // Re-encrypts saves named `from` (or `from.*`) as `to` (or `to.*`)
+    Rename { old_id: String, new_id: String, from: String, to: String },
+    // Re-encrypts saves with another platform's game code and ID
+    Convert { old_id: String, new_id: String, to_code: String },
+}
+
+impl Operation {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Operation::Resign { .. } => "_resigned",
+            Operation::Decrypt { .. } => "_decrypted",
+            Operation::Encrypt { .. } => "_encrypted",
+            Operation::Verify { .. } => "",
+            Operation::Rename { .. } => "_renamed",
+            Operation::Convert { .. } => "_converted",
+        }
+    }
+
+    // The name a save is written under, if the operation changes it
+    pub fn renamed(&self, name: &str) -> Option<String> {
+        match self {
+            Operation::Rename { from, to, .. } => rename(name, from, to),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Decrypt { .. } => "decrypt",
+            Operation::Encrypt { .. } => "encrypt",
+            Operation::Resign { .. } => "resign",
+            Operation::Verify { .. } => "verify",
+            Operation::Rename { .. } => "rename",
+            Operation::Convert { .. } => "convert",
+        }
+    }
+
+    // The ID the input is encrypted for and the one the output is encrypted for
+    pub fn ids(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            Operation::Decrypt { id } | Operation::Verify { id } => (Some(id), None),
+            Operation::Encrypt { id } => (None, Some(id)),
+            Operation::Resign { old_id, new_id }
+            | Operation::Rename { old_id, new_id, .. }
+            | Operation::Convert { old_id, new_id, .. } => (Some(old_id), Some(new_id)),
+        }
+    }
+
+    pub fn writes_output(&self) -> bool {
+        !matches!(self, Operation::Verify { .. })
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Operation::Resign { .. } => "Resigning",
+            Operation::Decrypt { .. } => "Decrypting",
+            Operation::Encrypt { .. } => "Encrypting",
+            Operation::Verify { .. } => "Verifying",
+            Operation::Rename { .. } => "Renaming",
+            Operation::Convert { .. } => "Converting",
+        }
+    }
+
+    // Returns the output and the file name the key was bound to. `logical`
+    // overrides that name: the name the input was encrypted under, or for
+    // Encrypt the name to encrypt the output under. Rename moves it along
+    // with the file.
+    fn transform(&self, data: &[u8], name: &str, code: &str, logical: Option<&str>) -> Result<(Vec<u8>, String), CryptoError> {
+        let decrypt = |id: &str| match logical {
+            Some(logical) => IdCrypto::decrypt_file(data, logical, code, id).map(|d| (d, logical.to_string())),
+            None => IdCrypto::decrypt_file_as(data, name, code, id),
+        };
+        match self {
+            Operation::Decrypt { id } => decrypt(id),
+            Operation::Encrypt { id } => {
+                let key_name = logical.unwrap_or(name);
+                IdCrypto::encrypt_file(data, key_name, code, id).map(|d| (d, key_name.to_string()))
+            }
+            Operation::Resign { old_id, new_id } => {
+                let (plain, key_name) = decrypt(old_id)?;
+                IdCrypto::encrypt_file(&plain, &key_name, code, new_id).map(|d| (d, key_name))
+            }
+            Operation::Verify { id } => decrypt(id).map(|(_, used)| (Vec::new(), used)),
+            Operation::Rename { old_id, new_id, .. } => {
+                // The slot moves, so the name the save decrypted under moves with it
+                let (plain, used) = decrypt(old_id)?;
+                let key_name = self.renamed(&used).unwrap_or(used);
+                IdCrypto::encrypt_file(&plain, &key_name, code, new_id).map(|d| (d, key_name))
+            }
+            Operation::Convert { old_id, new_id, to_code } => {
+                let (plain, key_name) = decrypt(old_id)?;
+                IdCrypto::encrypt_file(&plain, &key_name, to_code, new_id).map(|d| (d, key_name))
+            }
+        }
+    }
+
+    fn summary(&self, processed: usize) -> String {
+        match self {
+            Operation::Decrypt { id } => format!("Decrypted {} files from SteamID {}", processed, id),
+            Operation::Encrypt { id } => format!("Encrypted {} files for SteamID {}", processed, id),
+            Operation::Resign { old_id, new_id } => format!("Resigned {} files from SteamID {} to SteamID {}", processed, old_id, new_id),
+            Operation::Verify { id } => format!("Verified {} files for SteamID {}", processed, id),
+            Operation::Rename { old_id, new_id, from, to } if old_id == new_id => {
+                format!("Renamed {} files from {} to {} for SteamID {}", processed, from, to, new_id)
+            }
+            Operation::Rename { old_id, new_id, from, to } => {
+                format!("Renamed {} files from {} to {} and from SteamID {} to SteamID {}", processed, from, to, old_id, new_id)
+            }
+            Operation::Convert { old_id, new_id, to_code } => {
+                format!("Converted {} files from user ID {} to user ID {} (game code {})", processed, old_id, new_id, to_code)
+            }
+        }
+    }
+
+    fn done(&self, processed: usize) -> String {
+        match self {
+            Operation::Decrypt { .. } => format!("Successfully decrypted {} files", processed),
+            Operation::Encrypt { .. } => format!("Successfully encrypted {} files", processed),
+            Operation::Resign { .. } => format!("Successfully resigned {} files", processed),
+            Operation::Verify { .. } => format!("All {} files verified", processed),
+            Operation::Rename { .. } => format!("Successfully renamed {} files", processed),
+            Operation::Convert { .. } => format!("Successfully converted {} files", processed),
+        }
+    }
+}
+
+// "game" renames game.details and game.dat-backup, "game.details" only that file
+pub fn rename(name: &str, from: &str, to: &str) -> Option<String> {
+    if name == from {
+        return Some(to.to_string());
+    }
+    name.strip_prefix(from)
+        .filter(|rest| rest.starts_with('.'))
+        .map(|rest| format!("{}{}", to, rest))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileStatus {
+    Ok,
+    WrongKey,
+    // Verify: decrypts, but only under another name than the one the game loads
+    WrongName(String),
+    Truncated,
+    Unreadable(String),
+    Failed(String),
+    WriteFailed(String),
+    Skipped,
+    Copied,
+    Exists,
+}
+
+impl FileStatus {
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, FileStatus::Ok | FileStatus::Skipped | FileStatus::Copied | FileStatus::Exists)
+    }
+
+    // Stable name for machine-readable output
+    pub fn code(&self) -> &'static str {
+        match self {
+            FileStatus::Ok => "ok",
+            FileStatus::WrongKey => "wrong-key",
+            FileStatus::WrongName(_) => "wrong-name",
+            FileStatus::Truncated => "truncated",
+            FileStatus::Unreadable(_) => "unreadable",
+            FileStatus::Failed(_) => "failed",
+            FileStatus::WriteFailed(_) => "write-failed",
+            FileStatus::Skipped => "skipped",
+            FileStatus::Copied => "copied",
+            FileStatus::Exists => "exists",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            FileStatus::Ok => "ok".to_string(),
+            FileStatus::WrongKey => "wrong key (check the user ID, game and platform)".to_string(),
+            FileStatus::WrongName(name) => format!("only decrypts as {}, not under its own name", name),
+            FileStatus::Truncated => "too short to be an encrypted save (truncated or not encrypted)".to_string(),
+            FileStatus::Unreadable(e) => format!("unreadable: {}", e),
+            FileStatus::Failed(e) => format!("failed: {}", e),
+            FileStatus::WriteFailed(e) => format!("write failed: {}", e),
+            FileStatus::Skipped => "skipped (not an encrypted save)".to_string(),
+            FileStatus::Copied => "copied unchanged (not encrypted)".to_string(),
+            FileStatus::Exists => "kept the existing output file".to_string(),
+        }
+    }
+}
+
+impl From<CryptoError> for FileStatus {
+    fn from(err: CryptoError) -> Self {
+        match err {
+            CryptoError::AuthenticationFailed => FileStatus::WrongKey,
+            CryptoError::TooShort(_) => FileStatus::Truncated,
+            CryptoError::Io(e) => FileStatus::Unreadable(e.to_string()),
+            other => FileStatus::Failed(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileResult {
+    pub path: PathBuf,
+    pub status: FileStatus,
+    // Set when the key was bound to a name other than the file's own
+    pub key_name: Option<String>,
+}
+
+impl FileResult {
+    pub fn describe(&self) -> String {
+        match &self.key_name {
+            Some(name) => format!("{} (as {})", self.status.describe(), name),
+            None => self.status.describe(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub message: String,
+    pub files: Vec<FileResult>,
+    // Where the originals were copied before an in-place run
+    pub backup: Option<PathBuf>,
+    // The folder the results were written to
+    pub output: Option<PathBuf>,
+}
+
+impl BatchReport {
+    pub fn processed(&self) -> usize {
+        self.files.iter().filter(|f| f.status == FileStatus::Ok).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.files.iter().filter(|f| f.status.is_failure()).count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.files.iter().filter(|f| f.status == FileStatus::Skipped).count()
+    }
+
+    pub fn copied(&self) -> usize {
+        self.files.iter().filter(|f| f.status == FileStatus::Copied).count()
+    }
+
+    pub fn kept(&self) -> usize {
+        self.files.iter().filter(|f| f.status == FileStatus::Exists).count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    pub continue_on_error: bool,
+    pub copy_skipped: bool,
+    pub rules: FileRules,
+    // Paths relative to the input folder, mapped to the file name their key is bound to
+    pub logical_names: HashMap<PathBuf, String>,
+    pub conflicts: ConflictPolicy,
+    // Recorded in the run manifest
+    pub game: String,
+    pub platform: Option<Platform>,
+    // The platform a Convert run writes for
+    pub target_platform: Option<Platform>,
+    pub redact_ids: bool,
+}
+
+// What to do about files already in the output folder
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    // Writes to "<output> (2)" instead when the output folder already exists
+    NewFolder,
+    Abort,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 4] = [
+        ConflictPolicy::Overwrite,
+        ConflictPolicy::Skip,
+        ConflictPolicy::NewFolder,
+        ConflictPolicy::Abort,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "skip" => Some(ConflictPolicy::Skip),
+            "new-folder" => Some(ConflictPolicy::NewFolder),
+            "abort" => Some(ConflictPolicy::Abort),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Overwrite => "Overwrite existing files",
+            ConflictPolicy::Skip => "Keep existing files",
+            ConflictPolicy::NewFolder => "Write to a new folder",
+            ConflictPolicy::Abort => "Abort",
+        }
+    }
+
+    fn describe_conflict(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Overwrite | ConflictPolicy::NewFolder => "overwrites existing file",
+            ConflictPolicy::Skip => "exists, kept",
+            ConflictPolicy::Abort => "exists, aborts the run",
+        }
+    }
+
+    // The output folder itself, or the first free "<output> (N)" next to it
+    pub fn output_dir(&self, output: &Path) -> PathBuf {
+        if *self != ConflictPolicy::NewFolder || !output.exists() {
+            return output.to_path_buf();
+        }
+        let name = output.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        (2..).map(|n| output.with_file_name(format!("{} ({})", name, n)))
+            .find(|dir| !dir.exists())
+            .unwrap_or_else(|| output.to_path_buf())
+    }
+}
+
+// "sub/game.details-backup = game.details"
+pub fn parse_logical_name(entry: &str) -> Result<(PathBuf, String), String> {
+    let (path, name) = entry.split_once('=')
+        .ok_or_else(|| format!("Expected <file>=<name>, got '{}'", entry))?;
+    let (path, name) = (path.trim(), name.trim());
+    if path.is_empty() || name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("Expected <file>=<name>, got '{}'", entry));
+    }
+    Ok((PathBuf::from(path), name.to_string()))
+}
+
+
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    pub source: PathBuf,
+    pub target: Option<PathBuf>,
+    pub exists: bool,
+    pub key_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub files: Vec<PlannedFile>,
+    pub copied: Vec<PlannedFile>,
+    pub skipped: Vec<PathBuf>,
+    pub policy: ConflictPolicy,
+}
+
+impl Plan {
+    pub fn conflicts(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().chain(&self.copied).filter(|f| f.exists)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            text.push_str(&format!("{}\n", self.describe_file(file)));
+        }
+        for file in &self.copied {
+            text.push_str(&format!("{} (copied unchanged)\n", self.describe_file(file)));
+        }
+        for file in &self.skipped {
+            let source = file.strip_prefix(&self.input).unwrap_or(file);
+            text.push_str(&format!("{}: {}\n", source.display(), FileStatus::Skipped.describe()));
+        }
+        text.push_str(&self.summary());
+        text
+    }
+
+    pub fn describe_file(&self, file: &PlannedFile) -> String {
+        let source = file.source.strip_prefix(&self.input).unwrap_or(&file.source);
+        let mut text = match &file.target {
+            Some(target) if file.exists => format!("{} → {} ({})", source.display(), target.display(), self.policy.describe_conflict()),
+            Some(target) => format!("{} → {}", source.display(), target.display()),
+            None => source.display().to_string(),
+        };
+        if let Some(name) = &file.key_name {
+            text.push_str(&format!(" (as {})", name));
+        }
+        text
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} files to process, {} copied, {} skipped, {} conflicts",
+            self.files.len(),
+            self.copied.len(),
+            self.skipped.len(),
+            self.conflicts().count()
+        );
+        if self.conflicts().count() > 0 {
+            summary.push_str(&format!(" ({})", self.policy.label().to_lowercase()));
+        }
+        summary
+    }
+}
+
+pub fn plan(op: &Operation, input: &Path, output: &Path, options: &BatchOptions) -> Result<Plan, Box<dyn Error>> {
+    let scan = prepare_input(op, input, options)?;
+    let output = &options.conflicts.output_dir(output);
+    let planned = |file: PathBuf, renamed: Option<String>| -> Result<PlannedFile, Box<dyn Error>> {
+        let target = if op.writes_output() {
+            Some(target_path(input, output, &file, renamed)?)
+        } else {
+            None
+        };
+        let exists = target.as_ref().is_some_and(|t| t.exists());
+        let key_name = options.logical_names.get(file.strip_prefix(input)?).cloned();
+        Ok(PlannedFile { source: file, target, exists, key_name })
+    };
+
+    let files = scan.saves.into_iter()
+        .map(|file| {
+            let renamed = op.renamed(file_name(&file)?);
+            planned(file, renamed)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (mut copied, mut skipped) = (Vec::new(), Vec::new());
+    // Plaintext save files always belong in the output, unknown files only on request
+    for (file, copy) in scan.plaintext.into_iter().map(|f| (f, true))
+        .chain(scan.skipped.into_iter().map(|f| (f, options.copy_skipped))) {
+        if copy && op.writes_output() {
+            copied.push(planned(file, None)?);
+        } else {
+            skipped.push(file);
+        }
+    }
+
+    Ok(Plan { input: input.to_path_buf(), output: output.to_path_buf(), files, copied, skipped, policy: options.conflicts })
+}
+
+// What `process_in_place` would do: saves replace their originals whatever
+// the conflict policy, and every other file stays where it is
+pub fn plan_in_place(op: &Operation, input: &Path, options: &BatchOptions) -> Result<Plan, Box<dyn Error>> {
+    let options = BatchOptions { conflicts: ConflictPolicy::Overwrite, copy_skipped: false, ..options.clone() };
+    let mut plan = plan(op, input, input, &options)?;
+    plan.skipped.extend(plan.copied.drain(..).map(|f| f.source));
+    Ok(plan)
+}
+
+// Everything is written to a staging folder next to the output first and only
+// moved into the output once the whole batch succeeded, so a failed or
+// interrupted run leaves the output folder as it was.
+pub fn process(op: &Operation, input: &Path, output: &Path, code: &str, options: &BatchOptions) -> Result<BatchReport, Box<dyn Error>> {
+    if !op.writes_output() {
+        return process_into(op, input, output, code, options, &HashSet::new());
+    }
+
+    let plan = plan(op, input, output, options)?;
+    let conflicts: Vec<&PathBuf> = plan.conflicts().filter_map(|f| f.target.as_ref()).collect();
+    if options.conflicts == ConflictPolicy::Abort && !conflicts.is_empty() {
+        let names: Vec<String> = conflicts.iter()
+            .map(|t| t.strip_prefix(&plan.output).unwrap_or(t).display().to_string())
+            .collect();
+        return Err(format!("{} files already exist in {}: {}", names.len(), plan.output.display(), names.join(", ")).into());
+    }
+    // Targets relative to the output folder that are left as they are
+    let mut keep = HashSet::new();
+    if options.conflicts == ConflictPolicy::Skip {
+        for target in conflicts {
+            keep.insert(target.strip_prefix(&plan.output)?.to_path_buf());
+        }
+    }
+
+    let (requested, output) = (output, &plan.output);
+    let staging = unique_sibling(output, "_staging");
+    let result = process_into(op, input, &staging, code, options, &keep).and_then(|mut report| {
+        let files = staged_files(&staging)?;
+        commit(&staging, output, &files)?;
+        if output != requested {
+            report.message = format!("{} (written to {} since {} already exists)", report.message, output.display(), requested.display());
+        }
+        report.output = Some(output.clone());
+        Ok(report)
+    });
+    fs::remove_dir_all(&staging).ok();
+    result
+}
+
+fn process_into(op: &Operation, input: &Path, output: &Path, code: &str, options: &BatchOptions, keep: &HashSet<PathBuf>) -> Result<BatchReport, Box<dyn Error>> {
+    let scan = prepare_input(op, input, options)?;
+    if op.writes_output() {
+        fs::create_dir_all(output).map_err(|e| format!("Failed to create output directory: {}", e))?;
+    }
+    // A verify run is only useful as a complete pass/fail report
+    let continue_on_error = options.continue_on_error || !op.writes_output();
+
+    let mut results = Vec::new();
+    let mut log = String::new();
+
+    for file in &scan.saves {
+        let name = file_name(file)?;
+        log.push_str(&format!("{} {}... ", op.verb(), name));
+
+        let path = file.strip_prefix(input)?.to_path_buf();
+        let logical = options.logical_names.get(&path).map(String::as_str);
+        let (status, key_name) = if keep.contains(&output_relative(op, &path)?) {
+            (FileStatus::Exists, None)
+        } else {
+            process_file(op, input, output, code, file, name, logical)
+        };
+        let result = FileResult { path, status, key_name: key_name.filter(|k| k != name) };
+        log.push_str(&format!("{}\n", result.describe()));
+
+        if result.status.is_failure() && !continue_on_error {
+            return Err(format!("{}: {}", name, result.describe()).into());
+        }
+        results.push(result);
+    }
+
+    let copies = scan.plaintext.iter().map(|f| (f, true))
+        .chain(scan.skipped.iter().map(|f| (f, options.copy_skipped)));
+    for (file, copy) in copies {
+        let status = if copy && keep.contains(file.strip_prefix(input)?) {
+            FileStatus::Exists
+        } else if copy && op.writes_output() {
+            match output_path(input, output, file, None).and_then(|out| Ok(copy_synced(file, &out)?)) {
+                Ok(()) => FileStatus::Copied,
+                Err(e) => FileStatus::WriteFailed(e.to_string()),
+            }
+        } else {
+            FileStatus::Skipped
+        };
+        log.push_str(&format!("{}: {}\n", file_name(file)?, status.describe()));
+
+        if status.is_failure() && !continue_on_error {
+            return Err(format!("{}: {}", file_name(file)?, status.describe()).into());
+        }
+        results.push(FileResult { path: file.strip_prefix(input)?.to_path_buf(), status, key_name: None });
+    }
+
+    let mut report = BatchReport { message: String::new(), files: results, backup: None, output: None };
+    let processed = report.processed();
+    let mut summary = op.summary(processed);
+    let mut notes = Vec::new();
+    if report.failed() > 0 {
+        notes.push(format!("{} failed", report.failed()));
+    }
+    if report.copied() > 0 {
+        notes.push(format!("{} unencrypted files copied unchanged", report.copied()));
+    }
+    if report.skipped() > 0 {
+        notes.push(format!("{} skipped", report.skipped()));
+    }
+    if report.kept() > 0 {
+        notes.push(format!("{} existing output files kept", report.kept()));
+    }
+    if !notes.is_empty() {
+        summary.push_str(&format!(" ({})", notes.join(", ")));
+    }
+
+    if op.writes_output() {
+        let mut manifest = Manifest::new(op, code, options);
+        for file in &report.files {
+            let written = match file.status {
+                FileStatus::Ok => Some(output_relative(op, &file.path)?),
+                FileStatus::Copied => Some(file.path.clone()),
+                _ => None,
+            };
+            manifest.add(input, output, file, written);
+        }
+        manifest.write(output)?;
+        write_info(output, &summary, &log)?;
+    }
+    report.message = if report.is_success() && report.kept() == 0 {
+        op.done(processed)
+    } else {
+        summary
+    };
+    Ok(report)
+}
+
+// Processes the input folder over itself. Every file in it is first copied to
+// a timestamped backup folder next to it, the results are staged in another
+// sibling folder and then moved over the originals one by one.
+pub fn process_in_place(op: &Operation, input: &Path, code: &str, options: &BatchOptions) -> Result<BatchReport, Box<dyn Error>> {
+    if !op.writes_output() {
+        return Err("Nothing to write back, this operation doesn't change any file".into());
+    }
+    // "." has no parent to put the backup next to
+    let input = &fs::canonicalize(input)
+        .map_err(|e| format!("Failed to resolve {}: {}", input.display(), e))?;
+    prepare_input(op, input, options)?;
+
+    let backup = unique_sibling(input, "_backup");
+    let staging = unique_sibling(input, "_staging");
+    if backup.starts_with(input) || staging.starts_with(input) {
+        return Err(format!("Can't process {} in place, there is no folder next to it for the backup", input.display()).into());
+    }
+    if let Err(e) = copy_tree(input, &backup) {
+        fs::remove_dir_all(&backup).ok();
+        return Err(format!("Failed to back up the input folder: {}", e).into());
+    }
+
+    // Plaintext and unknown files stay where they are
+    let staged_options = BatchOptions { copy_skipped: false, ..options.clone() };
+    let mut report = match process(op, input, &staging, code, &staged_options) {
+        Ok(report) => report,
+        Err(e) => {
+            // Nothing was overwritten, so the backup isn't needed
+            fs::remove_dir_all(&staging).ok();
+            fs::remove_dir_all(&backup).ok();
+            return Err(e);
+        }
+    };
+
+    let replaced = replace_originals(op, input, &staging, &report);
+    // The backup holds the exact originals, so don't replace an INFO.txt that was among them
+    let info = fs::read_to_string(staging.join("INFO.txt")).unwrap_or_default();
+    let info = format!("Originals of {} backed up here before processing in place\n{}", input.display(), info);
+    fs::write(free_name(&backup, "INFO.txt", "INFO (in place).txt"), info).ok();
+    fs::copy(staging.join(MANIFEST_FILE), free_name(&backup, MANIFEST_FILE, "INFO (in place).json")).ok();
+    fs::remove_dir_all(&staging).ok();
+    if let Err(e) = replaced {
+        return Err(format!("{} (the originals are in {})", e, backup.display()).into());
+    }
+
+    report.message = format!("{} (originals backed up to {})", report.message, backup.display());
+    report.backup = Some(backup);
+    Ok(report)
+}
+
+fn free_name(dir: &Path, name: &str, fallback: &str) -> PathBuf {
+    if dir.join(name).exists() {
+        dir.join(fallback)
+    } else {
+        dir.join(name)
+    }
+}
+
+// Renamed saves replace their original under the new name
+fn replace_originals(op: &Operation, input: &Path, staging: &Path, report: &BatchReport) -> Result<(), Box<dyn Error>> {
+    let written: Vec<&FileResult> = report.files.iter().filter(|f| f.status == FileStatus::Ok).collect();
+    let targets = written.iter()
+        .map(|f| output_relative(op, &f.path))
+        .collect::<Result<Vec<_>, _>>()?;
+    commit(staging, input, &targets)?;
+    for (file, target) in written.iter().zip(&targets) {
+        if *target != file.path {
+            fs::remove_file(input.join(&file.path))?;
+        }
+    }
+    Ok(())
+}
+
+// Moves the staged files into the output folder. A new output folder is
+// renamed into place in one step; otherwise existing files are set aside
+// while the staged ones are moved in, and put back if any move fails.
+fn commit(staging: &Path, output: &Path, files: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    if !output.exists() {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(staging, output)
+            .map_err(|e| format!("Failed to move the results into {}: {}", output.display(), e))?;
+        return Ok(());
+    }
+
+    let aside = unique_sibling(staging, "_replaced");
+    let mut moved: Vec<(&PathBuf, bool)> = Vec::new();
+    let mut result = Ok(());
+    for file in files {
+        let target = output.join(file);
+        let replaces = target.exists();
+        let step = (|| -> std::io::Result<()> {
+            if replaces {
+                let old = aside.join(file);
+                fs::create_dir_all(old.parent().unwrap_or(&aside))?;
+                fs::rename(&target, &old)?;
+            }
+            fs::create_dir_all(target.parent().unwrap_or(output))?;
+            fs::rename(staging.join(file), &target)
+        })();
+        match step {
+            Ok(()) => moved.push((file, replaces)),
+            Err(e) => {
+                // The original may already be set aside
+                if replaces && !target.exists() {
+                    fs::rename(aside.join(file), &target).ok();
+                }
+                result = Err(format!("Failed to write {}: {}", target.display(), e));
+                break;
+            }
+        }
+    }
+
+    if result.is_err() {
+        for (file, replaced) in moved.iter().rev() {
+            let target = output.join(file);
+            if *replaced {
+                fs::rename(aside.join(file), &target).ok();
+            } else {
+                fs::remove_file(&target).ok();
+            }
+        }
+    }
+    fs::remove_dir_all(&aside).ok();
+    Ok(result?)
+}
+
+// Every file below the staging folder, relative to it
+fn staged_files(staging: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![staging.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path.strip_prefix(staging)?.to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
+
+// `<name><suffix>_<date>-<time>` next to `path`, numbered if a run in the
+// same second already took it
+pub(crate) fn unique_sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let mut sibling = output_dir(path, None, &format!("{}_{}", suffix, stamp));
+    for n in 2.. {
+        if !sibling.exists() {
+            break;
+        }
+        sibling = output_dir(path, None, &format!("{}_{}-{}", suffix, stamp, n));
+    }
+    sibling
+}
+
+pub(crate) fn write_synced(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+fn copy_synced(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::copy(from, to)?;
+    fs::File::open(to)?.sync_all()
+}
+
+// Writes to a temporary file next to the target and renames it into place,
+// so the target is never left half written
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    let result = write_synced(&temp, data).and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        fs::remove_file(&temp).ok();
+    }
+    result
+}
+
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            copy_tree(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn process_file(op: &Operation, input: &Path, output: &Path, code: &str, file: &Path, name: &str, logical: Option<&str>) -> (FileStatus, Option<String>) {
+    let (transformed, key_name) = match fs::read(file)
+        .map_err(CryptoError::from)
+        .and_then(|data| op.transform(&data, name, code, logical)) {
+        Ok(result) => result,
+        Err(e) => return (e.into(), None),
+    };
+
+    if !op.writes_output() {
+        // The game loads a save under its own name, so a match as another one doesn't pass
+        if key_name != logical.unwrap_or(name) {
+            return (FileStatus::WrongName(key_name), None);
+        }
+        return (FileStatus::Ok, Some(key_name));
+    }
+
+    let status = match output_path(input, output, file, op.renamed(name)).and_then(|out| Ok(write_synced(&out, &transformed)?)) {
+        Ok(()) => FileStatus::Ok,
+        Err(e) => FileStatus::WriteFailed(e.to_string()),
+    };
+    (status, Some(key_name))
+}
+
+fn prepare_input(op: &Operation, input: &Path, options: &BatchOptions) -> Result<Scan, Box<dyn Error>> {
+    let rules = &options.rules;
+    if !input.exists() {
+        return Err("Input path does not exist".into());
+    }
+
+    if input.is_file() {
+        return Err("Input path must be a directory, not a file".into());
+    }
+
+    let scan = scan(input, rules)?;
+    if scan.saves.is_empty() {
+        return Err(no_saves(rules).into());
+    }
+    for path in options.logical_names.keys() {
+        if !scan.saves.iter().any(|f| f.strip_prefix(input).is_ok_and(|f| f == path)) {
+            return Err(format!("Logical name given for {}, which is not an encrypted save in the input folder", path.display()).into());
+        }
+    }
+    check_renames(op, input, &scan, options.copy_skipped)?;
+    Ok(scan)
+}
+
+// A renamed save must not land on another file that is written to the output
+fn check_renames(op: &Operation, input: &Path, scan: &Scan, copy_skipped: bool) -> Result<(), Box<dyn Error>> {
+    let Operation::Rename { from, to, .. } = op else {
+        return Ok(());
+    };
+    if [from, to].iter().any(|n| n.is_empty() || n.contains(['/', '\\'])) {
+        return Err("File names to rename from and to must be plain, non-empty names".into());
+    }
+    if !scan.saves.iter().any(|f| file_name(f).is_ok_and(|n| op.renamed(n).is_some())) {
+        return Err(format!("No save file is named {} or {}.*", from, from).into());
+    }
+    let mut targets: HashMap<PathBuf, &Path> = HashMap::new();
+    let renamed = scan.saves.iter().map(|f| (f, true));
+    let copied = if copy_skipped { &scan.skipped[..] } else { &[] };
+    let unchanged = scan.plaintext.iter().chain(copied).map(|f| (f, false));
+    for (file, is_save) in renamed.chain(unchanged) {
+        let relative = file.strip_prefix(input)?;
+        let target = match is_save.then(|| op.renamed(file_name(file).ok()?)).flatten() {
+            Some(name) => relative.with_file_name(name),
+            None => relative.to_path_buf(),
+        };
+        if let Some(other) = targets.insert(target.clone(), relative) {
+            return Err(format!(
+                "{} and {} would both be written as {}",
+                other.display(), relative.display(), target.display()
+            ).into());
+        }
+    }
+    Ok(())
+}
+
+// Where a file relative to the input folder ends up relative to the output folder
+pub fn output_relative(op: &Operation, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(match op.renamed(file_name(path)?) {
+        Some(name) => path.with_file_name(name),
+        None => path.to_path_buf(),
+    })
+}
+
+fn file_name(file: &Path) -> Result<&str, Box<dyn Error>> {
+    Ok(file.file_name()
+        .ok_or("Invalid file name")?
+        .to_str()
+        .ok_or("Invalid file name encoding")?)
+}
+
+fn target_path(input: &Path, output: &Path, file: &Path, renamed: Option<String>) -> Result<PathBuf, Box<dyn Error>> {
+    let out = output.join(file.strip_prefix(input)?);
+    Ok(match renamed {
+        Some(name) => out.with_file_name(name),
+        None => out,
+    })
+}
+
+fn output_path(input: &Path, output: &Path, file: &Path, renamed: Option<String>) -> Result<PathBuf, Box<dyn Error>> {
+    let out = target_path(input, output, file, renamed)?;
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(out)
+}
+
+fn write_info(output: &Path, summary: &str, log: &str) -> Result<(), Box<dyn Error>> {
+    let ts: DateTime<Local> = Local::now();
+    let mut info = format!("Processing completed at: {}\n\n", ts.format("%Y-%m-%d %H:%M:%S"));
+    info.push_str(&format!("{}\n\n", summary));
+    info.push_str(log);
+    
+    write_synced(&output.join("INFO.txt"), info.as_bytes())?;
+    Ok(())
+}
+
+pub fn output_dir(input: &Path, base: Option<&Path>, suffix: &str) -> PathBuf {
+    let base = base.unwrap_or_else(|| input.parent().unwrap_or(input));
+    let name = input.file_name()
+        .unwrap_or_else(|| std::ffi::OsStr::new("output"))
+        .to_string_lossy();
+    
+    base.join(format!("{}{}", name, suffix))
+}
+
+fn no_saves(rules: &FileRules) -> String {
+    format!("No save files matching {} found in the directory", rules.encrypted.join(" / "))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scan {
+    pub saves: Vec<PathBuf>,
+    pub plaintext: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Scan {
+    fn add(&mut self, file: PathBuf, kind: FileKind) {
+        match kind {
+            FileKind::Encrypted => self.saves.push(file),
+            FileKind::Plaintext => self.plaintext.push(file),
+            FileKind::Unknown => self.skipped.push(file),
+            FileKind::Ignored => {}
+        }
+    }
+}
+
+pub fn scan(path: &Path, rules: &FileRules) -> Result<Scan, Box<dyn Error>> {
+    let mut scan = Scan::default();
+    if path.is_file() {
+        let name = Path::new(path.file_name().unwrap_or_default());
+        scan.add(path.to_path_buf(), rules.classify(name));
+    } else if path.is_dir() {
+        walk_dir(path, path, rules, &mut scan)?;
+    }
+    Ok(scan)
+}
+
+pub fn collect_files(path: &Path, rules: &FileRules) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let files = scan(path, rules)?.saves;
+    
+    if files.is_empty() {
+        return Err(no_saves(rules).into());
+    }
+    
+    Ok(files)
+}
+
+fn walk_dir(root: &Path, path: &Path, rules: &FileRules, scan: &mut Scan) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let p = entry.path();
+        if p.is_file() {
+            let kind = rules.classify(p.strip_prefix(root)?);
+            scan.add(p, kind);
+        } else if p.is_dir() {
+            walk_dir(root, &p, rules, scan)?;
+        }
+    }
+    Ok(())
+}
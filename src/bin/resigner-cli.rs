@@ -0,0 +1,515 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use resigner::batch::{self, BatchOptions, ConflictPolicy, FileStatus, Operation};
+use resigner::history::{self, History, HISTORY_FILE};
+use resigner::games::{FileRules, IdFormat, Platform, PlatformProfile, Registry, USER_GAMES_FILE};
+use resigner::identify::{classify_folder, detect_game, identify_owner, parse_candidates, read_candidates};
+use resigner::ids::{normalize_id, SteamId};
+use resigner::locate::find_save_slots;
+use resigner::steam::{local_steam_accounts, local_steam_ids};
+
+const USAGE: &str = "\
+Usage:
+  resigner-cli decrypt --game <game> [--platform steam|gog] --user-id <id> --input <dir> [--output <dir> | --in-place] [--on-conflict <policy>]
+  resigner-cli encrypt --game <game> [--platform steam|gog] --user-id <id> --input <dir> [--output <dir> | --in-place] [--on-conflict <policy>] [--logical-name <file>=<name>]
+  resigner-cli resign  --game <game> [--platform steam|gog] --old-id <id> --new-id <id> --input <dir> [--output <dir> | --in-place] [--on-conflict <policy>]
+  resigner-cli rename  --game <game> [--platform steam|gog] --old-id <id> [--new-id <id>] --from <name> --to <name> --input <dir> [--output <dir> | --in-place] [--on-conflict <policy>]
+  resigner-cli convert --game <game> --platform <from> --to-platform <to> --old-id <id> --new-id <id> --input <dir> [--output <dir> | --in-place] [--on-conflict <policy>]
+  resigner-cli verify   --game <game> [--platform steam|gog] --user-id <id> --input <dir>
+  resigner-cli check    --game <game> [--platform steam|gog] [--user-id <id>] --input <dir>
+  resigner-cli detect   --user-id <id> --input <dir>
+  resigner-cli accounts
+  resigner-cli locate   --game <game> [--platform steam|gog]
+  resigner-cli history  [--history-file <file>]
+  resigner-cli restore  --run <id|latest> [--force] [--history-file <file>]
+  resigner-cli identify --game <game> [--platform steam|gog] --input <dir> [--candidates <id,id,...>] [--candidates-file <file>] [--local-steam]
+
+If --output is omitted, files are written next to the input folder with a
+_decrypted / _encrypted / _resigned / _renamed / _converted suffix.
+--on-conflict decides what happens to files that already exist in the output
+folder: overwrite (default), skip (keep the existing file), new-folder (write
+to <output> (2) instead) or abort (stop before writing anything). --dry-run
+lists the conflicts.
+Files are staged next to the output folder and only moved into it once the
+whole batch has succeeded, so a failed run leaves the output folder as it was.
+With --keep-going the files that did succeed are still moved in, and the run
+exits with 1.
+With --in-place the input folder itself is updated instead: every file in it
+is first copied to a <input>_backup_<date>-<time> folder next to it, and each
+processed file then replaces its original atomically.
+
+Next to INFO.txt every run writes INFO.json, a manifest with the tool
+version, operation, game, platform, game code, IDs and the size and SHA-256
+of each file before and after. --redact-ids masks all but the last four
+characters of the IDs in it.
+
+Every run that writes files is recorded in resigner_history.json in the
+working directory (or --history-file) with the SHA-256 of each input and
+output file. Output files a run overwrites, INFO.txt and INFO.json included,
+are first copied to an <output>_backup_<date>-<time> folder. history lists
+the recorded runs, and restore puts back the exact bytes every file had before the given run, removes
+the files it created and checks the result against the recorded hashes. It
+refuses runs that were already restored, and runs whose files have changed
+since (e.g. overwritten by a later run) unless --force is given.
+
+rename moves saves to another file slot: every save named <from> or
+<from>.<ext> is decrypted and encrypted again as <to> or <to>.<ext>, for
+--new-id if given or the same ID otherwise. The other files keep their names.
+
+convert moves saves between storefront copies of a game: they are decrypted
+with the --platform game code and --old-id, and encrypted again with the
+--to-platform game code and --new-id (e.g. GogUser for DOOM Eternal on GOG).
+
+verify decrypts every save in memory and reports which ones authenticate,
+without writing anything.
+
+check prints whether each save is encrypted for the given ID, encrypted for
+another ID or game, or plaintext. Without --user-id it can only guess between
+encrypted and plaintext.
+
+detect lists the game and platform combinations whose game code decrypts the
+saves for the given user ID.
+
+accounts lists the Steam accounts that have signed in on this machine, from
+Steam's config/loginusers.vdf and userdata folders.
+
+locate lists the save folders of a game found on this machine (Steam userdata,
+Proton prefixes and Saved Games), with the ID each one belongs to when the
+location tells.
+
+identify finds which of the candidate IDs signed the saves. Without any
+candidate option it tries the Steam accounts found on this machine. The same
+candidate options can replace --user-id (decrypt) or --old-id (resign, rename,
+convert).
+
+Pass --dry-run to any command that processes files to list the files
+that would be processed or skipped, where each would be written and which
+existing files would be overwritten, without touching anything.
+
+Steam IDs may be given as a SteamID64, STEAM_0:Y:Z, [U:1:N], a bare account
+ID or a steamcommunity.com/profiles/ link; they are converted to SteamID64.
+IDs are checked against the platform before anything is processed. Where a
+release always uses the same ID (GogUser for DOOM Eternal on GOG) it is filled
+in when the ID option is left out.
+
+Game profiles are built in and can be extended or overridden with a JSON file
+passed as --games-file (default: resigner_games.json in the working directory,
+if present).
+
+Each game profile lists which files are encrypted saves, which are plaintext
+(always copied to the output unchanged) and which are ignored. Pass
+--copy-skipped to also copy files that match none of these (screenshots,
+metadata) unchanged, so the output folder can replace the input folder.
+
+Saves are bound to their file name. A save that doesn't decrypt under its own
+name is also tried under its canonical name (game.details-backup,
+game.details.bak or game - Copy.details as game.details), and the name that
+worked is reported. verify counts such a save as a failure, since the game
+loads it under its own name.
+--logical-name <file>=<name> sets the name explicitly: the name <file> is
+encrypted under, or will be for encrypt. resign and convert keep that name,
+rename moves it to the new slot along with the file. <file> is relative to
+the input folder; repeat it for several files.
+
+Pass --keep-going to process the remaining files when one of them fails;
+every failure is then listed at the end and in INFO.txt.
+
+Exit codes:
+  0  every file was processed
+  1  one or more files could not be processed, or no candidate ID matched
+  2  invalid arguments";
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+
+enum Command {
+    Decrypt,
+    Encrypt,
+    Resign,
+    Identify,
+    Detect,
+    Check,
+    Verify,
+    Rename,
+    Convert,
+    Accounts,
+    Locate,
+    History,
+    Restore,
+}
+
+#[derive(Default)]
+struct Args {
+    game: Option<String>,
+    platform: Option<String>,
+    user_id: Option<String>,
+    old_id: Option<String>,
+    new_id: Option<String>,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    keep_going: bool,
+    dry_run: bool,
+    in_place: bool,
+    on_conflict: ConflictPolicy,
+    redact_ids: bool,
+    copy_skipped: bool,
+    candidates: Vec<String>,
+    candidates_file: Option<PathBuf>,
+    local_steam: bool,
+    games_file: Option<PathBuf>,
+    logical_names: HashMap<PathBuf, String>,
+    from: Option<String>,
+    to: Option<String>,
+    to_platform: Option<String>,
+    history_file: Option<PathBuf>,
+    run: Option<String>,
+    force: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match execute(args) {
+        Ok(msg) => {
+            println!("{}", msg);
+            ExitCode::SUCCESS
+        }
+        Err((code, msg)) => {
+            eprintln!("error: {}", msg);
+            if code == EXIT_USAGE {
+                eprintln!("\n{}", USAGE);
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn execute(args: Vec<String>) -> Result<String, (u8, String)> {
+    let usage = |msg: String| (EXIT_USAGE, msg);
+
+    let mut iter = args.into_iter();
+    let command = match iter.next().as_deref() {
+        Some("decrypt") => Command::Decrypt,
+        Some("encrypt") => Command::Encrypt,
+        Some("resign") => Command::Resign,
+        Some("identify") => Command::Identify,
+        Some("detect") => Command::Detect,
+        Some("check") => Command::Check,
+        Some("verify") => Command::Verify,
+        Some("rename") => Command::Rename,
+        Some("convert") => Command::Convert,
+        Some("accounts") => Command::Accounts,
+        Some("locate") => Command::Locate,
+        Some("history") => Command::History,
+        Some("restore") => Command::Restore,
+        Some("help") | Some("-h") | Some("--help") => return Ok(USAGE.to_string()),
+        Some(other) => return Err(usage(format!("Unknown command '{}'", other))),
+        None => return Err(usage("Missing command".to_string())),
+    };
+
+    let mut parsed = Args::default();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| usage(format!("Missing value for {}", flag)));
+        match flag.as_str() {
+            "--game" => parsed.game = Some(value()?),
+            "--platform" => parsed.platform = Some(value()?),
+            "--to-platform" => parsed.to_platform = Some(value()?),
+            "--user-id" => parsed.user_id = Some(value()?),
+            "--old-id" => parsed.old_id = Some(value()?),
+            "--new-id" => parsed.new_id = Some(value()?),
+            "--from" => parsed.from = Some(value()?),
+            "--to" => parsed.to = Some(value()?),
+            "--input" => parsed.input = Some(PathBuf::from(value()?)),
+            "--output" => parsed.output = Some(PathBuf::from(value()?)),
+            "--keep-going" => parsed.keep_going = true,
+            "--dry-run" => parsed.dry_run = true,
+            "--in-place" => parsed.in_place = true,
+            "--redact-ids" => parsed.redact_ids = true,
+            "--on-conflict" => {
+                let name = value()?;
+                parsed.on_conflict = ConflictPolicy::from_name(&name)
+                    .ok_or_else(|| usage(format!("Unknown conflict policy '{}' (expected overwrite, skip, new-folder or abort)", name)))?;
+            }
+            "--copy-skipped" => parsed.copy_skipped = true,
+            "--candidates" => parsed.candidates.extend(parse_candidates(&value()?)),
+            "--candidates-file" => parsed.candidates_file = Some(PathBuf::from(value()?)),
+            "--local-steam" => parsed.local_steam = true,
+            "--games-file" => parsed.games_file = Some(PathBuf::from(value()?)),
+            "--history-file" => parsed.history_file = Some(PathBuf::from(value()?)),
+            "--run" => parsed.run = Some(value()?),
+            "--force" => parsed.force = true,
+            "--logical-name" => {
+                let (path, name) = batch::parse_logical_name(&value()?).map_err(usage)?;
+                parsed.logical_names.insert(path, name);
+            }
+            _ => return Err(usage(format!("Unknown option '{}'", flag))),
+        }
+    }
+
+    let history_file = parsed.history_file.clone().unwrap_or_else(|| PathBuf::from(HISTORY_FILE));
+    if let Command::History = command {
+        let history = History::load(&history_file).map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        if history.runs.is_empty() {
+            return Err((EXIT_FAILED, format!("No runs recorded in {}", history_file.display())));
+        }
+        let lines: Vec<String> = history.runs.iter().rev().map(|r| r.describe()).collect();
+        return Ok(lines.join("\n"));
+    }
+
+    if let Command::Restore = command {
+        let id = parsed.run.ok_or_else(|| usage("Missing --run".to_string()))?;
+        let history = History::load(&history_file).map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        let run = history.get(&id)
+            .ok_or_else(|| (EXIT_FAILED, format!("No run {} in {}", id, history_file.display())))?;
+        let msg = history::restore(run, parsed.force).map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        History::mark_restored(&history_file, &run.id).map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        return Ok(msg);
+    }
+
+    let registry = match &parsed.games_file {
+        Some(path) => {
+            let mut registry = Registry::builtin();
+            registry.merge_file(path).map_err(|e| usage(e.to_string()))?;
+            registry
+        }
+        None => Registry::load(&PathBuf::from(USER_GAMES_FILE)).map_err(|e| usage(e.to_string()))?,
+    };
+
+    if let Command::Accounts = command {
+        let accounts = local_steam_accounts();
+        if accounts.is_empty() {
+            return Err((EXIT_FAILED, "No Steam accounts found on this machine".to_string()));
+        }
+        let lines: Vec<String> = accounts.iter().map(|a| a.label()).collect();
+        return Ok(lines.join("\n"));
+    }
+
+    if let Command::Detect = command {
+        let input = parsed.input.ok_or_else(|| usage("Missing --input".to_string()))?;
+        let id = parsed.user_id.ok_or_else(|| usage("Missing --user-id".to_string()))?;
+        return detect(&input, &id, &registry);
+    }
+
+    let game_query = parsed.game.ok_or_else(|| usage("Missing --game".to_string()))?;
+    let game = registry.find(&game_query).ok_or_else(|| {
+        let known: Vec<&str> = registry.games().iter().map(|g| g.slug.as_str()).collect();
+        usage(format!("Unknown game '{}' (expected one of: {})", game_query, known.join(", ")))
+    })?;
+
+    if let Command::Locate = command {
+        let only = match &parsed.platform {
+            Some(p) => Some(Platform::from_name(p).ok_or_else(|| usage(format!("Unknown platform '{}'", p)))?),
+            None => None,
+        };
+        let lines: Vec<String> = find_save_slots(game)
+            .iter()
+            .filter(|slot| only.as_ref().is_none_or(|p| *p == slot.platform))
+            .map(|slot| slot.describe())
+            .collect();
+        if lines.is_empty() {
+            return Err((EXIT_FAILED, format!("No {} saves found on this machine", game.name)));
+        }
+        return Ok(lines.join("\n"));
+    }
+
+    let platform = match parsed.platform {
+        Some(p) => Platform::from_name(&p).ok_or_else(|| usage(format!("Unknown platform '{}'", p)))?,
+        None => Platform::Steam,
+    };
+    let profile = game.platform(&platform)
+        .ok_or_else(|| usage(format!("{} is not supported on {}", game.name, platform.label())))?;
+    let code = profile.code.clone();
+
+    let input = parsed.input.ok_or_else(|| usage("Missing --input".to_string()))?;
+
+    // IDs are checked against the platform's format, Steam IDs in any common
+    // form are normalised to SteamID64
+    let check_id_for = |profile: &PlatformProfile, label: &str, id: &str| -> Result<String, (u8, String)> {
+        normalize_id(profile, id).map_err(|e| usage(format!("Invalid {}{}: {}", label, profile.id_format.label(), e)))
+    };
+    let check_id = |label: &str, id: &str| check_id_for(profile, label, id);
+    let fixed_id = profile.fixed_id().map(str::to_string);
+
+    let has_candidates = !parsed.candidates.is_empty() || parsed.candidates_file.is_some() || parsed.local_steam;
+    let identify = || -> Result<String, (u8, String)> {
+        let mut candidates = parsed.candidates.clone();
+        if let Some(file) = &parsed.candidates_file {
+            let from_file = read_candidates(file)
+                .map_err(|e| usage(format!("Failed to read {}: {}", file.display(), e)))?;
+            candidates.extend(from_file);
+        }
+        if parsed.local_steam || !has_candidates {
+            candidates.extend(local_steam_ids());
+        }
+        if profile.id_format == IdFormat::Steam64 {
+            for id in candidates.iter_mut() {
+                if let Ok(steam_id) = SteamId::parse(id) {
+                    *id = steam_id.to_string();
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        candidates.retain(|id| seen.insert(id.clone()));
+
+        match identify_owner(&input, &code, &candidates, &game.files) {
+            Ok(Some(owner)) => {
+                eprintln!("Owner {} verified against {}", owner.id, owner.file.display());
+                Ok(owner.id)
+            }
+            Ok(None) => Err((EXIT_FAILED, format!("None of the {} candidate IDs can decrypt these saves", candidates.len()))),
+            Err(e) => Err((EXIT_FAILED, e.to_string())),
+        }
+    };
+
+    let op = match command {
+        Command::Identify => return identify(),
+        Command::Detect | Command::Accounts | Command::Locate | Command::History | Command::Restore => unreachable!(),
+        Command::Check => {
+            let id = parsed.user_id.as_deref().map(|id| check_id("", id)).transpose()?;
+            return check(&input, &code, id.as_deref(), &game.files);
+        }
+        Command::Decrypt if parsed.user_id.is_none() && has_candidates => Operation::Decrypt { id: identify()? },
+        Command::Decrypt | Command::Encrypt | Command::Verify => {
+            let id = parsed.user_id.clone().or(fixed_id).ok_or_else(|| usage("Missing --user-id".to_string()))?;
+            let id = check_id("", &id)?;
+            match command {
+                Command::Decrypt => Operation::Decrypt { id },
+                Command::Encrypt => Operation::Encrypt { id },
+                _ => Operation::Verify { id },
+            }
+        }
+        Command::Resign => {
+            let new_id = parsed.new_id.clone().ok_or_else(|| usage("Missing --new-id".to_string()))?;
+            let old_id = match parsed.old_id.clone().or(fixed_id) {
+                Some(id) => id,
+                None if has_candidates => identify()?,
+                None => return Err(usage("Missing --old-id".to_string())),
+            };
+            let old_id = check_id("Old ", &old_id)?;
+            let new_id = check_id("New ", &new_id)?;
+            if old_id == new_id {
+                return Err(usage("Old and New IDs cannot be the same".to_string()));
+            }
+            Operation::Resign { old_id, new_id }
+        }
+        Command::Rename => {
+            let from = parsed.from.clone().ok_or_else(|| usage("Missing --from".to_string()))?;
+            let to = parsed.to.clone().ok_or_else(|| usage("Missing --to".to_string()))?;
+            let old_id = match parsed.old_id.clone().or(fixed_id) {
+                Some(id) => id,
+                None if has_candidates => identify()?,
+                None => return Err(usage("Missing --old-id".to_string())),
+            };
+            let new_id = parsed.new_id.clone().unwrap_or_else(|| old_id.clone());
+            let old_id = check_id("Old ", &old_id)?;
+            let new_id = check_id("New ", &new_id)?;
+            if from == to && old_id == new_id {
+                return Err(usage("Renaming to the same name and ID changes nothing".to_string()));
+            }
+            Operation::Rename { old_id, new_id, from, to }
+        }
+        Command::Convert => {
+            let name = parsed.to_platform.as_deref().ok_or_else(|| usage("Missing --to-platform".to_string()))?;
+            let to_platform = Platform::from_name(name).ok_or_else(|| usage(format!("Unknown platform '{}'", name)))?;
+            if to_platform == platform {
+                return Err(usage("--platform and --to-platform are the same, use resign instead".to_string()));
+            }
+            let to_profile = game.platform(&to_platform)
+                .ok_or_else(|| usage(format!("{} is not supported on {}", game.name, to_platform.label())))?;
+            let to_code = to_profile.code.clone();
+            let new_id = parsed.new_id.clone()
+                .or_else(|| to_profile.fixed_id().map(str::to_string))
+                .ok_or_else(|| usage("Missing --new-id".to_string()))?;
+            let old_id = match parsed.old_id.clone().or(fixed_id) {
+                Some(id) => id,
+                None if has_candidates => identify()?,
+                None => return Err(usage("Missing --old-id".to_string())),
+            };
+            let old_id = check_id("Old ", &old_id)?;
+            let new_id = check_id_for(to_profile, "New ", &new_id)?;
+            Operation::Convert { old_id, new_id, to_code }
+        }
+    };
+
+    if parsed.in_place && (parsed.output.is_some() || !op.writes_output()) {
+        return Err(usage("--in-place can't be combined with --output or verify".to_string()));
+    }
+    let output = match parsed.output {
+        Some(output) => output,
+        None if parsed.in_place => input.clone(),
+        None => batch::output_dir(&input, None, op.suffix()),
+    };
+    let options = BatchOptions {
+        continue_on_error: parsed.keep_going,
+        copy_skipped: parsed.copy_skipped,
+        rules: game.files.clone(),
+        logical_names: parsed.logical_names,
+        conflicts: parsed.on_conflict,
+        game: game.slug.clone(),
+        platform: Some(platform.clone()),
+        target_platform: match command {
+            Command::Convert => parsed.to_platform.as_deref().and_then(Platform::from_name),
+            _ => None,
+        },
+        redact_ids: parsed.redact_ids,
+    };
+    if parsed.dry_run {
+        let plan = if parsed.in_place {
+            batch::plan_in_place(&op, &input, &options)
+        } else {
+            batch::plan(&op, &input, &output, &options)
+        };
+        let plan = plan.map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        return Ok(plan.describe());
+    }
+
+    let report = if op.writes_output() {
+        let (report, run) = history::run(&op, &input, &output, &code, &options, parsed.in_place)
+            .map_err(|e| (EXIT_FAILED, e.to_string()))?;
+        match History::append(&history_file, run) {
+            Ok(id) => eprintln!("Recorded as run {} (undo with: resigner-cli restore --run {})", id, id),
+            Err(e) => eprintln!("warning: run not recorded: {}", e),
+        }
+        report
+    } else {
+        batch::process(&op, &input, &output, &code, &options).map_err(|e| (EXIT_FAILED, e.to_string()))?
+    };
+
+    for file in report.files.iter().filter(|f| f.status != FileStatus::Ok || f.key_name.is_some()) {
+        eprintln!("{}: {}", file.path.display(), file.describe());
+    }
+
+    let msg = if op.writes_output() && !parsed.in_place {
+        format!("{} → {}", report.message, report.output.as_deref().unwrap_or(&output).display())
+    } else {
+        report.message.clone()
+    };
+    if report.is_success() {
+        Ok(msg)
+    } else {
+        Err((EXIT_FAILED, msg))
+    }
+}
+
+fn detect(input: &Path, id: &str, registry: &Registry) -> Result<String, (u8, String)> {
+    let detection = detect_game(input, id, registry)
+        .map_err(|e| (EXIT_FAILED, e.to_string()))?
+        .ok_or_else(|| (EXIT_FAILED, format!("No supported game decrypts these saves for {}", id)))?;
+
+    eprintln!("Verified against {}", detection.file.display());
+    let lines: Vec<String> = detection.matches
+        .iter()
+        .filter_map(|m| registry.get(&m.slug).map(|g| format!("{} ({}, {})", g.name, m.platform.label(), m.code)))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn check(input: &Path, code: &str, id: Option<&str>, rules: &FileRules) -> Result<String, (u8, String)> {
+    let states = classify_folder(input, code, id, rules).map_err(|e| (EXIT_FAILED, e.to_string()))?;
+    let lines: Vec<String> = states
+        .iter()
+        .map(|f| format!("{}: {}", f.path.display(), f.state.describe()))
+        .collect();
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,337 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+pub const USER_GAMES_FILE: &str = "resigner_games.json";
+
+const BUILTIN_GAMES: &str = include_str!("games.json");
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Steam,
+    GOG,
+}
+
+impl Platform {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "steam" => Some(Platform::Steam),
+            "gog" => Some(Platform::GOG),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Platform::Steam => "Steam",
+            Platform::GOG => "GOG",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdFormat {
+    Steam64,
+    Galaxy,
+    Any,
+    // Only the profile's known IDs are valid
+    Fixed,
+}
+
+impl IdFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            IdFormat::Steam64 => "SteamID",
+            IdFormat::Galaxy => "Galaxy ID",
+            IdFormat::Any | IdFormat::Fixed => "User ID",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    Encrypted,
+    Plaintext,
+    Ignored,
+    Unknown,
+}
+
+// Glob patterns ("*" and "?") matched case-insensitively against the file
+// name, or against the path relative to the save folder if they contain a "/"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileRules {
+    pub encrypted: Vec<String>,
+    pub plaintext: Vec<String>,
+    pub ignored: Vec<String>,
+}
+
+impl Default for FileRules {
+    fn default() -> Self {
+        Self {
+            encrypted: ["*.bin", "*.dat", "*.details", "*.details-backup", "*.dat-backup"]
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            plaintext: Vec::new(),
+            ignored: Vec::new(),
+        }
+    }
+}
+
+impl FileRules {
+    // Ignored wins over plaintext, plaintext over encrypted
+    pub fn classify(&self, relative: &Path) -> FileKind {
+        let any = |patterns: &[String]| patterns.iter().any(|p| matches_path(p, relative));
+        if any(&self.ignored) {
+            FileKind::Ignored
+        } else if any(&self.plaintext) {
+            FileKind::Plaintext
+        } else if any(&self.encrypted) {
+            FileKind::Encrypted
+        } else {
+            FileKind::Unknown
+        }
+    }
+}
+
+fn matches_path(pattern: &str, relative: &Path) -> bool {
+    let text = if pattern.contains('/') {
+        relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    } else {
+        match relative.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => return false,
+        }
+    };
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    glob(&pattern, &text)
+}
+
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last "*" swallow one more character
+                Some((bp, bt)) => {
+                    backtrack = Some((bp, bt + 1));
+                    p = bp + 1;
+                    t = bt + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownId {
+    pub id: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlatformProfile {
+    pub platform: Platform,
+    pub code: String,
+    pub id_format: IdFormat,
+    // IDs a release is known to hardcode, offered as suggestions
+    #[serde(default)]
+    pub known_ids: Vec<KnownId>,
+    #[serde(default)]
+    pub save_locations: Vec<String>,
+}
+
+impl PlatformProfile {
+    // The ID to fill in when the platform only ever uses one
+    pub fn fixed_id(&self) -> Option<&str> {
+        match (self.id_format, &self.known_ids[..]) {
+            (IdFormat::Fixed, [only]) => Some(&only.id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameProfile {
+    pub name: String,
+    pub slug: String,
+    #[serde(default)]
+    pub steam_app_id: Option<u32>,
+    #[serde(default)]
+    pub files: FileRules,
+    pub platforms: Vec<PlatformProfile>,
+}
+
+impl GameProfile {
+    pub fn platform(&self, platform: &Platform) -> Option<&PlatformProfile> {
+        self.platforms.iter().find(|p| &p.platform == platform)
+    }
+
+    pub fn supports(&self, platform: &Platform) -> bool {
+        self.platform(platform).is_some()
+    }
+
+    pub fn code(&self, platform: &Platform) -> Option<&str> {
+        self.platform(platform).map(|p| p.code.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GamesFile {
+    games: Vec<GameProfile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Registry {
+    games: Vec<GameProfile>,
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let file: GamesFile = serde_json::from_str(BUILTIN_GAMES).expect("Invalid built-in games.json");
+        Self { games: file.games }
+    }
+
+    // Built-in profiles plus the user's file, if there is one
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut registry = Self::builtin();
+        if path.exists() {
+            registry.merge_file(path)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: GamesFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid game profiles in {}: {}", path.display(), e))?;
+        self.merge(file.games)
+            .map_err(|e| format!("Invalid game profiles in {}: {}", path.display(), e).into())
+    }
+
+    // Profiles with a known slug replace the existing one, new slugs are appended
+    pub fn merge(&mut self, games: Vec<GameProfile>) -> Result<(), String> {
+        for game in games {
+            if game.slug.is_empty() {
+                return Err(format!("'{}' has no slug", game.name));
+            }
+            if game.platforms.is_empty() {
+                return Err(format!("'{}' has no platforms", game.slug));
+            }
+            if game.files.encrypted.is_empty() {
+                return Err(format!("'{}' has no encrypted file patterns", game.slug));
+            }
+            if let Some(p) = game.platforms.iter().find(|p| p.code.is_empty()) {
+                return Err(format!("'{}' has no game code for {:?}", game.slug, p.platform));
+            }
+            if let Some(p) = game.platforms.iter().find(|p| p.id_format == IdFormat::Fixed && p.known_ids.is_empty()) {
+                return Err(format!("'{}' uses a fixed ID on {:?} but lists no known_ids", game.slug, p.platform));
+            }
+
+            match self.games.iter_mut().find(|g| g.slug == game.slug) {
+                Some(existing) => *existing = game,
+                None => self.games.push(game),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn games(&self) -> &[GameProfile] {
+        &self.games
+    }
+
+    // The patterns of every profile, for when the game isn't known yet
+    pub fn file_rules(&self) -> FileRules {
+        let mut rules = FileRules { encrypted: Vec::new(), ..FileRules::default() };
+        for files in self.games.iter().map(|g| &g.files) {
+            for (all, patterns) in [
+                (&mut rules.encrypted, &files.encrypted),
+                (&mut rules.plaintext, &files.plaintext),
+                (&mut rules.ignored, &files.ignored),
+            ] {
+                for pattern in patterns {
+                    if !all.contains(pattern) {
+                        all.push(pattern.clone());
+                    }
+                }
+            }
+        }
+        rules
+    }
+
+    pub fn get(&self, slug: &str) -> Option<&GameProfile> {
+        self.games.iter().find(|g| g.slug == slug)
+    }
+
+    pub fn find(&self, query: &str) -> Option<&GameProfile> {
+        let query = query.to_lowercase();
+        self.games.iter().find(|g| g.slug == query || g.name.to_lowercase() == query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob(&pattern, &text)
+    }
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(matches("*.bin", "profile.bin"));
+        assert!(!matches("*.bin", "profile.bin-backup"));
+        assert!(matches("*.details*", "game.details-backup"));
+        assert!(matches("?ame.dat", "game.dat"));
+        assert!(!matches("?ame.dat", "ame.dat"));
+        assert!(matches("a*b*c", "axxbyyc"));
+        assert!(matches("a*b*c", "abc"));
+        assert!(!matches("a*b*c", "acb"));
+        assert!(matches("*", ""));
+        assert!(matches("**", "anything"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+        assert!(!matches("game.dat", "game.dat2"));
+    }
+
+    #[test]
+    fn glob_backtracks_past_partial_matches() {
+        assert!(matches("*.dat", "a.dat.dat"));
+        assert!(matches("*ab", "aab"));
+        assert!(matches("*a*a", "banana"));
+        assert!(!matches("*a*x", "banana"));
+    }
+
+    #[test]
+    fn patterns_match_the_name_or_the_whole_path() {
+        assert!(matches_path("*.BIN", Path::new("PROFILE/profile.bin")));
+        assert!(matches_path("profile/*.bin", Path::new("PROFILE/profile.bin")));
+        assert!(!matches_path("profile/*.bin", Path::new("profile.bin")));
+        // "*" doesn't stop at folder separators
+        assert!(matches_path("*/profile.bin", Path::new("a/b/profile.bin")));
+    }
+}
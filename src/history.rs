@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use crate::batch::{self, BatchOptions, BatchReport, ConflictPolicy, FileStatus, Operation};
+use crate::manifest::{sha256_hex, MANIFEST_FILE};
+
+pub const HISTORY_FILE: &str = "resigner_history.json";
+
+const REPORTS: [&str; 2] = ["INFO.txt", MANIFEST_FILE];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub input: PathBuf,
+    pub input_sha256: String,
+    pub output: PathBuf,
+    pub output_sha256: String,
+    // What was at `output` before the run, None if there was nothing
+    pub previous_sha256: Option<String>,
+}
+
+// INFO.txt and INFO.json a run wrote to its output folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportRecord {
+    pub path: PathBuf,
+    pub sha256: String,
+    pub previous_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub time: String,
+    pub summary: String,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub in_place: bool,
+    // Pre-run copies of the files the run replaced, laid out like the output folder
+    pub backup: Option<PathBuf>,
+    pub output_existed: bool,
+    pub files: Vec<FileRecord>,
+    #[serde(default)]
+    pub reports: Vec<ReportRecord>,
+    #[serde(default)]
+    pub restored: Option<String>,
+}
+
+impl RunRecord {
+    pub fn describe(&self) -> String {
+        let mut text = format!("{}  {}  {}", self.id, self.time, self.summary);
+        if let Some(time) = &self.restored {
+            text.push_str(&format!(" (restored {})", time));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    pub runs: Vec<RunRecord>,
+}
+
+impl History {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid run history in {}: {}", path.display(), e))?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        batch::write_atomic(path, json.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    // "latest" is the most recent run
+    pub fn get(&self, id: &str) -> Option<&RunRecord> {
+        match id {
+            "latest" => self.runs.last(),
+            id => self.runs.iter().find(|r| r.id == id),
+        }
+    }
+
+    // Adds a run to the history file, giving it an ID no other run has
+    pub fn append(path: &Path, mut run: RunRecord) -> Result<String, Box<dyn Error>> {
+        let mut history = Self::load(path)?;
+        let base = run.id.clone();
+        for n in 2.. {
+            if history.get(&run.id).is_none() {
+                break;
+            }
+            run.id = format!("{}-{}", base, n);
+        }
+        let id = run.id.clone();
+        history.runs.push(run);
+        history.save(path)?;
+        Ok(id)
+    }
+
+    pub fn mark_restored(path: &Path, id: &str) -> Result<(), Box<dyn Error>> {
+        let mut history = Self::load(path)?;
+        if let Some(run) = history.runs.iter_mut().find(|r| r.id == id) {
+            run.restored = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+        history.save(path)
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String, Box<dyn Error>> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(sha256_hex(&data))
+}
+
+// Runs a batch and describes everything it changed, so it can be undone.
+// Output files the run is about to overwrite are copied to a backup folder
+// next to the output first; in-place runs back up the whole input folder.
+pub fn run(op: &Operation, input: &Path, output: &Path, code: &str, options: &BatchOptions, in_place: bool) -> Result<(BatchReport, RunRecord), Box<dyn Error>> {
+    let stamp = Local::now();
+    let requested = output;
+    // The folder the batch will pick for this conflict policy
+    let output = if in_place {
+        input.to_path_buf()
+    } else {
+        options.conflicts.output_dir(output)
+    };
+    let output = output.as_path();
+    let output_existed = output.exists();
+
+    let mut backup = None;
+    let mut previous = HashMap::new();
+    if !in_place {
+        // Every run rewrites the reports, other files only when they are overwritten
+        let mut targets: Vec<PathBuf> = REPORTS.iter().map(|name| output.join(name)).collect();
+        if options.conflicts != ConflictPolicy::Skip {
+            let plan = batch::plan(op, input, output, options)?;
+            targets.extend(plan.conflicts().filter_map(|f| f.target.clone()));
+        }
+        let dir = batch::unique_sibling(output, "_backup");
+        for target in targets.iter().filter(|t| t.exists()) {
+            let relative = target.strip_prefix(output)?;
+            let copy = dir.join(relative);
+            if let Some(parent) = copy.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(target, &copy).map_err(|e| format!("Failed to back up {}: {}", target.display(), e))?;
+            previous.insert(relative.to_path_buf(), hash_file(&copy)?);
+            backup = Some(dir.clone());
+        }
+    }
+
+    let result = if in_place {
+        batch::process_in_place(op, input, code, options)
+    } else {
+        batch::process(op, input, requested, code, options)
+    };
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            if let Some(dir) = &backup {
+                fs::remove_dir_all(dir).ok();
+            }
+            return Err(e);
+        }
+    };
+    if in_place {
+        backup = report.backup.clone();
+    }
+
+    // Absolute paths, so the run can be restored from any working directory
+    let input = &fs::canonicalize(input)?;
+    let output = &fs::canonicalize(output)?;
+    let backup = backup.map(fs::canonicalize).transpose()?;
+
+    let mut files = Vec::new();
+    // In place, only the saves that were written back changed anything
+    let changed = report.files.iter()
+        .filter(|f| f.status == FileStatus::Ok || (f.status == FileStatus::Copied && !in_place));
+    for file in changed {
+        // Copies keep their name
+        let relative = match file.status {
+            FileStatus::Copied => file.path.clone(),
+            _ => batch::output_relative(op, &file.path)?,
+        };
+        let (input_sha256, previous_sha256) = match &backup {
+            Some(dir) if in_place => {
+                let replaced = dir.join(&relative);
+                let previous = if replaced.exists() { Some(hash_file(&replaced)?) } else { None };
+                (hash_file(&dir.join(&file.path))?, previous)
+            }
+            _ => (hash_file(&input.join(&file.path))?, previous.get(&relative).cloned()),
+        };
+        files.push(FileRecord {
+            input: input.join(&file.path),
+            input_sha256,
+            output: output.join(&relative),
+            output_sha256: hash_file(&output.join(&relative))?,
+            previous_sha256,
+        });
+    }
+
+    // In place, the reports go to the backup folder, which restoring doesn't touch
+    let mut reports = Vec::new();
+    if !in_place {
+        for name in REPORTS.iter().filter(|name| output.join(name).exists()) {
+            reports.push(ReportRecord {
+                path: output.join(name),
+                sha256: hash_file(&output.join(name))?,
+                previous_sha256: previous.get(Path::new(name)).cloned(),
+            });
+        }
+    }
+
+    let run = RunRecord {
+        id: stamp.format("%Y%m%d-%H%M%S").to_string(),
+        time: stamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        summary: report.message.clone(),
+        input: input.to_path_buf(),
+        output: output.to_path_buf(),
+        in_place,
+        backup,
+        output_existed,
+        files,
+        reports,
+        restored: None,
+    };
+    Ok((report, run))
+}
+
+// Puts back the exact bytes every file the run changed had before it, and
+// removes the files it created. Nothing is touched unless every file still
+// holds what the run wrote (`force` skips that check) and every backup
+// matches its recorded hash.
+pub fn restore(run: &RunRecord, force: bool) -> Result<String, Box<dyn Error>> {
+    if let Some(time) = &run.restored {
+        return Err(format!("Run {} was already restored on {}", run.id, time).into());
+    }
+
+    // Every path the run changed: what it held before and what the run left there
+    let mut changes: Vec<(&Path, Option<&str>, Option<&str>)> = Vec::new();
+    for file in &run.files {
+        changes.push((&file.output, file.previous_sha256.as_deref(), Some(&file.output_sha256)));
+        // A renamed save in place leaves its original name empty
+        if run.in_place && file.input != file.output {
+            changes.push((&file.input, Some(&file.input_sha256), None));
+        }
+    }
+    for report in &run.reports {
+        changes.push((&report.path, report.previous_sha256.as_deref(), Some(&report.sha256)));
+    }
+
+    let mut changed = Vec::new();
+    for (path, _, after) in &changes {
+        let current = if path.exists() { Some(hash_file(path)?) } else { None };
+        if current.as_deref() != *after {
+            changed.push(path.display().to_string());
+        }
+    }
+    if !changed.is_empty() && !force {
+        return Err(format!(
+            "{} files changed since run {} (a later run or the game may have written them): {}. Restore with force to overwrite them anyway",
+            changed.len(), run.id, changed.join(", ")
+        ).into());
+    }
+
+    let mut restores = Vec::new();
+    let mut removals = Vec::new();
+    for (path, before, _) in changes {
+        let Some(hash) = before else {
+            removals.push(path);
+            continue;
+        };
+        let dir = run.backup.as_ref().ok_or("This run has no backup to restore from")?;
+        let copy = dir.join(path.strip_prefix(&run.output)?);
+        let data = fs::read(&copy).map_err(|e| format!("Failed to read backup {}: {}", copy.display(), e))?;
+        if sha256_hex(&data) != hash {
+            return Err(format!("Backup {} doesn't match the hash recorded before the run", copy.display()).into());
+        }
+        restores.push((path, hash, data));
+    }
+
+    for (path, hash, data) in &restores {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        batch::write_atomic(path, data).map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+        if hash_file(path)? != *hash {
+            return Err(format!("{} doesn't match its recorded hash after restoring", path.display()).into());
+        }
+    }
+    for path in &removals {
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+    }
+
+    // An output folder the run created goes away once it's empty again
+    if !run.in_place && !run.output_existed {
+        remove_empty_dirs(&run.output);
+    }
+
+    Ok(format!(
+        "Restored {} files and removed {} files created by run {}, all matching their recorded hashes",
+        restores.len(), removals.len(), run.id
+    ))
+}
+
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            if entry.path().is_dir() {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    fs::remove_dir(dir).ok();
+}
@@ -0,0 +1,190 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::batch;
+use crate::games::{FileRules, Platform, Registry};
+use crate::ids::normalize_id;
+use crate::logic::{CryptoError, IdCrypto, NONCE_AND_TAG_TOTAL_LENGTH, is_file_encrypted};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMatch {
+    pub slug: String,
+    pub platform: Platform,
+    pub code: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub matches: Vec<GameMatch>,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncryptionState {
+    EncryptedForKey,
+    EncryptedUnknownKey,
+    Plaintext,
+}
+
+impl EncryptionState {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EncryptionState::EncryptedForKey => "encrypted for this ID",
+            EncryptionState::EncryptedUnknownKey => "encrypted for another ID or game",
+            EncryptionState::Plaintext => "plaintext",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileState {
+    pub path: PathBuf,
+    pub state: EncryptionState,
+}
+
+#[derive(Debug, Clone)]
+pub struct Owner {
+    pub id: String,
+    pub file: PathBuf,
+}
+
+pub fn parse_candidates(text: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        for id in line.split([',', ';', ' ', '\t']).map(str::trim).filter(|s| !s.is_empty()) {
+            if !ids.iter().any(|existing| existing == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+pub fn read_candidates(path: &Path) -> std::io::Result<Vec<String>> {
+    Ok(parse_candidates(&fs::read_to_string(path)?))
+}
+
+pub fn find_owner(data: &[u8], file_name: &str, code: &str, candidates: &[String]) -> Result<Option<String>, CryptoError> {
+    for id in candidates {
+        match IdCrypto::decrypt_file_as(data, file_name, code, id) {
+            Ok(_) => return Ok(Some(id.clone())),
+            Err(CryptoError::AuthenticationFailed) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+pub fn identify_owner(input: &Path, code: &str, candidates: &[String], rules: &FileRules) -> Result<Option<Owner>, Box<dyn Error>> {
+    if candidates.is_empty() {
+        return Err("No candidate IDs to try".into());
+    }
+
+    let found = probe(input, rules, |data, name| find_owner(data, name, code, candidates))?;
+    Ok(found.map(|(id, file)| Owner { id, file }))
+}
+
+pub fn find_games(data: &[u8], file_name: &str, user_id: &str, registry: &Registry) -> Result<Vec<GameMatch>, CryptoError> {
+    let mut verified: Vec<(String, String, bool)> = Vec::new();
+    let mut matches = Vec::new();
+
+    for game in registry.games() {
+        for profile in &game.platforms {
+            let code = profile.code.clone();
+            // In the form the platform derives keys from, e.g. [U:1:N] as SteamID64
+            let Ok(id) = normalize_id(profile, user_id) else {
+                continue;
+            };
+            // Several entries share a game code, only decrypt once per code and ID
+            let ok = match verified.iter().find(|(c, i, _)| *c == code && *i == id) {
+                Some((_, _, ok)) => *ok,
+                None => {
+                    let ok = match IdCrypto::decrypt_file_as(data, file_name, &code, &id) {
+                        Ok(_) => true,
+                        Err(CryptoError::AuthenticationFailed) => false,
+                        Err(e) => return Err(e),
+                    };
+                    verified.push((code.clone(), id, ok));
+                    ok
+                }
+            };
+            if ok {
+                matches.push(GameMatch { slug: game.slug.clone(), platform: profile.platform.clone(), code });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+pub fn detect_game(input: &Path, user_id: &str, registry: &Registry) -> Result<Option<Detection>, Box<dyn Error>> {
+    let found = probe(input, &registry.file_rules(), |data, name| {
+        find_games(data, name, user_id, registry).map(|m| (!m.is_empty()).then_some(m))
+    })?;
+    Ok(found.map(|(matches, file)| Detection { matches, file }))
+}
+
+// A successful decrypt is proof, anything else falls back to the entropy heuristic
+pub fn classify(data: &[u8], file_name: &str, code: &str, user_id: Option<&str>) -> EncryptionState {
+    if data.len() < NONCE_AND_TAG_TOTAL_LENGTH {
+        return EncryptionState::Plaintext;
+    }
+
+    if let Some(id) = user_id
+        && IdCrypto::decrypt_file_as(data, file_name, code, id).is_ok() {
+        return EncryptionState::EncryptedForKey;
+    }
+
+    if is_file_encrypted(data) {
+        EncryptionState::EncryptedUnknownKey
+    } else {
+        EncryptionState::Plaintext
+    }
+}
+
+pub fn classify_folder(input: &Path, code: &str, user_id: Option<&str>, rules: &FileRules) -> Result<Vec<FileState>, Box<dyn Error>> {
+    let mut states = Vec::new();
+    for file in batch::collect_files(input, rules)? {
+        let name = file.file_name().and_then(|n| n.to_str()).ok_or("Invalid file name encoding")?;
+        let data = fs::read(&file).map_err(|e| format!("Failed to read file {}: {}", name, e))?;
+        states.push(FileState {
+            path: file.strip_prefix(input)?.to_path_buf(),
+            state: classify(&data, name, code, user_id),
+        });
+    }
+    Ok(states)
+}
+
+// How many saves `probe` tries before concluding nothing matches
+const PROBE_LIMIT: usize = 3;
+
+// Runs `check` against the encrypted saves in `input`, smallest first since
+// every attempt decrypts the whole file. A file that doesn't match may be a
+// stray file the rules picked up, so the next ones are tried too. Files too
+// short to be encrypted don't count towards the limit.
+fn probe<T>(
+    input: &Path,
+    rules: &FileRules,
+    mut check: impl FnMut(&[u8], &str) -> Result<Option<T>, CryptoError>,
+) -> Result<Option<(T, PathBuf)>, Box<dyn Error>> {
+    let mut files = batch::collect_files(input, rules)?;
+    files.sort_by_key(|f| fs::metadata(f).map(|m| m.len()).unwrap_or(u64::MAX));
+
+    let mut tried = 0;
+    for file in files {
+        let Some(name) = file.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let data = fs::read(&file)?;
+        match check(&data, name) {
+            Ok(Some(found)) => return Ok(Some((found, file))),
+            Ok(None) => tried += 1,
+            Err(CryptoError::TooShort(_)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+        if tried == PROBE_LIMIT {
+            break;
+        }
+    }
+    Ok(None)
+}
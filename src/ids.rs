@@ -0,0 +1,251 @@
+use crate::games::{IdFormat, PlatformProfile};
+
+pub fn validate_steam_id(id: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err("SteamID cannot be empty".to_string());
+    }
+    
+    if id.len() != 17 {
+        return Err("SteamID must be exactly 17 digits long".to_string());
+    }
+    
+    if !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err("SteamID must contain only numbers".to_string());
+    }
+    
+    let num: u64 = id.parse().map_err(|_| "Invalid SteamID format")?;
+    
+    if !id.starts_with("7656119") {
+        return Err("SteamID must start with 7656119 (Steam64 format)".to_string());
+    }
+    
+    if num < STEAM_ID64_BASE {
+        return Err("SteamID appears to be invalid (too small for Steam64 format)".to_string());
+    }
+    
+    if num > 76561999999999999 {
+        return Err("SteamID appears to be invalid (too large for Steam64 format)".to_string());
+    }
+    
+    Ok(())
+}
+
+pub const STEAM_ID64_BASE: u64 = 76561197960265728;
+
+pub fn steam_id_from_account_id(account_id: u32) -> String {
+    SteamId::from_account_id(account_id).to_string()
+}
+
+const PROFILE_URL: &str = "https://steamcommunity.com/profiles/";
+
+// Any of the textual forms a SteamID is shared in, normalised to SteamID64
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SteamId(u64);
+
+impl SteamId {
+    pub fn from_account_id(account_id: u32) -> Self {
+        Self(STEAM_ID64_BASE + account_id as u64)
+    }
+
+    // Accepts 76561197960287930, STEAM_0:0:11101, [U:1:22202], a bare account
+    // ID (22202) and steamcommunity.com/profiles/ links
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("SteamID cannot be empty".to_string());
+        }
+
+        if let Some(i) = text.find("steamcommunity.com/") {
+            let path = &text[i + "steamcommunity.com/".len()..];
+            if let Some(rest) = path.strip_prefix("profiles/") {
+                let id = rest.split(['/', '?', '#']).next().unwrap_or("");
+                return Self::from_id64(id);
+            }
+            return Err("Custom profile URLs can't be resolved offline, use the steamcommunity.com/profiles/ link or SteamID64".to_string());
+        }
+
+        let upper = text.to_uppercase();
+        if let Some(rest) = upper.strip_prefix("STEAM_") {
+            let parts: Vec<&str> = rest.split(':').collect();
+            let [universe, y, z] = parts[..] else {
+                return Err("Expected STEAM_X:Y:Z".to_string());
+            };
+            if !matches!(universe, "0" | "1") || !matches!(y, "0" | "1") {
+                return Err("Expected STEAM_0:Y:Z or STEAM_1:Y:Z with Y being 0 or 1".to_string());
+            }
+            let z: u32 = z.parse().map_err(|_| "Invalid account number in STEAM_X:Y:Z")?;
+            let account_id = z.checked_mul(2)
+                .and_then(|n| n.checked_add(y.parse::<u32>().unwrap_or(0)))
+                .ok_or("Account number in STEAM_X:Y:Z is too large")?;
+            return Ok(Self::from_account_id(account_id));
+        }
+
+        let steam3 = upper.trim_start_matches('[').trim_end_matches(']');
+        if let Some(rest) = steam3.strip_prefix("U:") {
+            let Some(("1", account)) = rest.split_once(':') else {
+                return Err("Expected [U:1:N]".to_string());
+            };
+            let account_id: u32 = account.parse().map_err(|_| "Invalid account ID in [U:1:N]")?;
+            return Ok(Self::from_account_id(account_id));
+        }
+
+        if text.chars().all(|c| c.is_ascii_digit()) && text.len() < 17 {
+            let account_id: u32 = text.parse().map_err(|_| "Account ID is too large, expected a 17-digit SteamID64")?;
+            return Ok(Self::from_account_id(account_id));
+        }
+
+        Self::from_id64(text)
+    }
+
+    fn from_id64(id: &str) -> Result<Self, String> {
+        validate_steam_id(id)?;
+        id.parse().map(Self).map_err(|_| "Invalid SteamID format".to_string())
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    pub fn account_id(&self) -> u32 {
+        (self.0 - STEAM_ID64_BASE) as u32
+    }
+
+    pub fn steam2(&self) -> String {
+        format!("STEAM_0:{}:{}", self.account_id() & 1, self.account_id() >> 1)
+    }
+
+    pub fn steam3(&self) -> String {
+        format!("[U:1:{}]", self.account_id())
+    }
+
+    pub fn profile_url(&self) -> String {
+        format!("{}{}", PROFILE_URL, self.0)
+    }
+}
+
+impl std::fmt::Display for SteamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for SteamId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// Checks an ID against the format the platform uses and returns it in the
+// form the key is derived from
+pub fn normalize_id(profile: &PlatformProfile, id: &str) -> Result<String, String> {
+    let id = id.trim();
+    if id.is_empty() {
+        return Err("ID cannot be empty".to_string());
+    }
+
+    match profile.id_format {
+        IdFormat::Steam64 => SteamId::parse(id).map(|id| id.to_string()),
+        IdFormat::Galaxy => {
+            if !id.chars().all(|c| c.is_ascii_digit()) {
+                return Err("Galaxy ID must contain only numbers".to_string());
+            }
+            match id.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(id.to_string()),
+                _ => Err("Galaxy ID is out of range".to_string()),
+            }
+        }
+        IdFormat::Fixed => {
+            if let Some(known) = profile.known_ids.iter().find(|k| k.id == id) {
+                return Ok(known.id.clone());
+            }
+            let expected: Vec<&str> = profile.known_ids.iter().map(|k| k.id.as_str()).collect();
+            match profile.known_ids.iter().find(|k| k.id.eq_ignore_ascii_case(id)) {
+                Some(known) => Err(format!("IDs are case-sensitive, did you mean {}?", known.id)),
+                None => Err(format!("this release always uses {}", expected.join(" or "))),
+            }
+        }
+        IdFormat::Any => {
+            if id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err("ID cannot contain spaces".to_string());
+            }
+            Ok(id.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GABEN: u64 = 76561197960287930;
+
+    fn parse(text: &str) -> u64 {
+        SteamId::parse(text).unwrap().as_u64()
+    }
+
+    #[test]
+    fn converts_between_forms() {
+        let id = SteamId::from_account_id(22202);
+        assert_eq!(id.as_u64(), GABEN);
+        assert_eq!(id.account_id(), 22202);
+        assert_eq!(id.steam2(), "STEAM_0:0:11101");
+        assert_eq!(id.steam3(), "[U:1:22202]");
+        assert_eq!(id.profile_url(), "https://steamcommunity.com/profiles/76561197960287930");
+        assert_eq!(id.to_string(), "76561197960287930");
+        assert_eq!(SteamId::from_account_id(22203).steam2(), "STEAM_0:1:11101");
+    }
+
+    #[test]
+    fn parses_steam2() {
+        // Account ID is Z * 2 + Y, whatever the universe digit
+        assert_eq!(parse("STEAM_0:0:11101"), GABEN);
+        assert_eq!(parse("STEAM_1:0:11101"), GABEN);
+        assert_eq!(parse("steam_0:1:11101"), GABEN + 1);
+        assert_eq!(parse("STEAM_0:1:0"), 76561197960265729);
+        assert!(SteamId::parse("STEAM_2:0:11101").is_err());
+        assert!(SteamId::parse("STEAM_0:2:11101").is_err());
+        assert!(SteamId::parse("STEAM_0:0").is_err());
+        assert!(SteamId::parse("STEAM_0:0:abc").is_err());
+        assert!(SteamId::parse("STEAM_0:0:2147483648").is_err());
+    }
+
+    #[test]
+    fn parses_steam3() {
+        assert_eq!(parse("[U:1:22202]"), GABEN);
+        assert_eq!(parse("U:1:22202"), GABEN);
+        assert_eq!(parse(" [u:1:22202] "), GABEN);
+        assert!(SteamId::parse("[U:2:22202]").is_err());
+        assert!(SteamId::parse("[U:1:]").is_err());
+        assert!(SteamId::parse("[U:1:4294967296]").is_err());
+    }
+
+    #[test]
+    fn parses_account_ids_and_id64() {
+        assert_eq!(parse("22202"), GABEN);
+        assert_eq!(parse("76561197960287930"), GABEN);
+        assert!(SteamId::parse("4294967296").is_err());
+        assert!(SteamId::parse("").is_err());
+        assert!(SteamId::parse("gaben").is_err());
+    }
+
+    #[test]
+    fn parses_profile_urls() {
+        assert_eq!(parse("https://steamcommunity.com/profiles/76561197960287930"), GABEN);
+        assert_eq!(parse("steamcommunity.com/profiles/76561197960287930/"), GABEN);
+        assert_eq!(parse("https://steamcommunity.com/profiles/76561197960287930?l=english"), GABEN);
+        assert!(SteamId::parse("https://steamcommunity.com/id/gabelogannewell").is_err());
+        assert!(SteamId::parse("https://steamcommunity.com/profiles/12345").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_id64() {
+        assert!(validate_steam_id("76561197960265728").is_ok());
+        assert!(validate_steam_id("76561197960265727").is_err());
+        assert!(validate_steam_id("76561200000000000").is_err());
+        assert!(validate_steam_id("7656119796028793").is_err());
+        assert!(validate_steam_id("765611979602879301").is_err());
+        assert!(SteamId::parse("86561197960287930").is_err());
+    }
+}
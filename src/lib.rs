@@ -0,0 +1,16 @@
+//! Encrypt, decrypt and resign idTech 7/8 SaveData files.
+//!
+//! Used by both the `resigner` GUI and the `resigner-cli` binary.
+
+pub mod logic;
+pub mod games;
+pub mod ids;
+pub mod batch;
+pub mod identify;
+pub mod steam;
+pub mod locate;
+pub mod history;
+pub mod manifest;
+
+pub use logic::{CryptoError, IdCrypto};
+pub use games::{GameProfile, Platform, Registry};
@@ -0,0 +1,228 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::games::{FileKind, GameProfile, Platform, PlatformProfile};
+use crate::ids::{steam_id_from_account_id, validate_steam_id};
+use crate::steam::{parse_vdf, steam_roots};
+
+// How deep below a save location slots are looked for
+const MAX_DEPTH: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct SaveSlot {
+    pub platform: Platform,
+    pub path: PathBuf,
+    // The ID the saves are signed with, when the location tells
+    pub owner: Option<String>,
+    pub files: usize,
+    pub modified: Option<SystemTime>,
+}
+
+impl SaveSlot {
+    pub fn describe(&self) -> String {
+        let owner = self.owner.as_deref().unwrap_or("unknown owner");
+        format!("{} ({}, {}, {} files)", self.path.display(), self.platform.label(), owner, self.files)
+    }
+}
+
+// Folders a location template can refer to
+#[derive(Debug, Clone, Default)]
+pub struct Folders {
+    pub steam: Vec<PathBuf>,
+    pub saved_games: Vec<PathBuf>,
+    pub documents: Vec<PathBuf>,
+}
+
+impl Folders {
+    // On Windows the user's own folders, elsewhere the Proton prefix Steam
+    // created for the game in every library
+    pub fn detect(platform: &Platform, steam_app_id: Option<u32>) -> Self {
+        let steam = steam_roots();
+        let mut folders = Folders { steam: steam.clone(), ..Default::default() };
+
+        if cfg!(windows) {
+            if let Some(profile) = env::var_os("USERPROFILE").map(PathBuf::from) {
+                folders.saved_games.push(profile.join("Saved Games"));
+                folders.documents.push(profile.join("Documents"));
+            }
+        } else if *platform == Platform::Steam
+            && let Some(app_id) = steam_app_id {
+            for library in steam.iter().flat_map(|root| library_folders(root)) {
+                let user = library
+                    .join("steamapps/compatdata")
+                    .join(app_id.to_string())
+                    .join("pfx/drive_c/users/steamuser");
+                folders.saved_games.push(user.join("Saved Games"));
+                folders.documents.push(user.join("Documents"));
+            }
+        }
+        folders
+    }
+}
+
+// The Steam root itself plus the libraries listed in libraryfolders.vdf
+pub fn library_folders(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    let Ok(text) = fs::read_to_string(root.join("steamapps/libraryfolders.vdf")) else {
+        return libraries;
+    };
+    let Ok(vdf) = parse_vdf(&text) else {
+        return libraries;
+    };
+
+    let entries = vdf.get("libraryfolders").map(|f| f.entries()).unwrap_or_default();
+    for (_, library) in entries {
+        if let Some(path) = library.value("path").map(PathBuf::from)
+            && path.is_dir()
+            && !libraries.iter().any(|l| same_dir(l, &path)) {
+            libraries.push(path);
+        }
+    }
+    libraries
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// Existing folders a template expands to, with the owner the path implies.
+// {account_id} matches every Steam account folder under userdata, {steam_id}
+// every folder named after a SteamID64.
+pub fn expand_location(template: &str, folders: &Folders) -> Vec<(PathBuf, Option<String>)> {
+    let (bases, rest) = match template.split_once('}') {
+        Some(("{steam", rest)) => (&folders.steam[..], rest),
+        Some(("{saved_games", rest)) => (&folders.saved_games[..], rest),
+        Some(("{documents", rest)) => (&folders.documents[..], rest),
+        // A literal path, possibly with {account_id} further down
+        _ => {
+            let (base, rest) = template.split_at(template.find("/{").unwrap_or(template.len()));
+            return expand_in(&[PathBuf::from(base)], rest);
+        }
+    };
+    expand_in(bases, rest)
+}
+
+fn expand_in(bases: &[PathBuf], rest: &str) -> Vec<(PathBuf, Option<String>)> {
+    let mut found = Vec::new();
+    for base in bases {
+        let mut paths = vec![(base.clone(), None)];
+        for part in rest.split(['/', '\\']).filter(|p| !p.is_empty()) {
+            paths = paths.into_iter().flat_map(|(path, owner)| expand_part(&path, part, owner)).collect();
+        }
+        found.extend(paths.into_iter().filter(|(path, _)| path.is_dir()));
+    }
+    found
+}
+
+fn expand_part(path: &Path, part: &str, owner: Option<String>) -> Vec<(PathBuf, Option<String>)> {
+    if part != "{account_id}" && part != "{steam_id}" {
+        return vec![(path.join(part), owner)];
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            if part == "{steam_id}" {
+                return validate_steam_id(&name).is_ok().then(|| (e.path(), Some(name)));
+            }
+            let account: u32 = name.parse().ok()?;
+            (account != 0).then(|| (e.path(), Some(steam_id_from_account_id(account))))
+        })
+        .collect()
+}
+
+pub fn find_save_slots(game: &GameProfile) -> Vec<SaveSlot> {
+    find_save_slots_with(game, |platform| Folders::detect(platform, game.steam_app_id))
+}
+
+// Slots of every platform, each folder listed once
+pub fn find_save_slots_with(game: &GameProfile, folders: impl Fn(&Platform) -> Folders) -> Vec<SaveSlot> {
+    let mut slots: Vec<SaveSlot> = Vec::new();
+    for profile in &game.platforms {
+        for slot in find_slots_in(game, profile, &folders(&profile.platform)) {
+            match slots.iter_mut().find(|s| same_dir(&s.path, &slot.path)) {
+                // A folder named after the ID a release hardcodes is that release's
+                Some(found) if profile.known_ids.iter().any(|k| Some(&k.id) == slot.owner.as_ref()) => *found = slot,
+                Some(_) => {}
+                None => slots.push(slot),
+            }
+        }
+    }
+    slots
+}
+
+// Every folder below the profile's save locations that directly holds saves
+pub fn find_slots_in(game: &GameProfile, profile: &PlatformProfile, folders: &Folders) -> Vec<SaveSlot> {
+    // Releases that always use the same ID own every save they write
+    let known_owner = match &profile.known_ids[..] {
+        [only] => Some(only.id.clone()),
+        _ => None,
+    };
+
+    let mut slots: Vec<SaveSlot> = Vec::new();
+    for template in &profile.save_locations {
+        for (location, owner) in expand_location(template, folders) {
+            let owner = owner.or_else(|| known_owner.clone());
+            collect_slots(game, profile, &location, owner, 0, &mut slots);
+        }
+    }
+    slots
+}
+
+fn collect_slots(
+    game: &GameProfile,
+    profile: &PlatformProfile,
+    dir: &Path,
+    owner: Option<String>,
+    depth: usize,
+    slots: &mut Vec<SaveSlot>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files = 0;
+    let mut modified: Option<SystemTime> = None;
+    let mut subdirs = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if game.files.classify(Path::new(&entry.file_name())) == FileKind::Encrypted {
+            files += 1;
+            let time = entry.metadata().and_then(|m| m.modified()).ok();
+            modified = modified.max(time);
+        }
+    }
+
+    if files > 0 && !slots.iter().any(|s| same_dir(&s.path, dir)) {
+        slots.push(SaveSlot {
+            platform: profile.platform.clone(),
+            path: dir.to_path_buf(),
+            owner: owner.clone(),
+            files,
+            modified,
+        });
+    }
+    if depth < MAX_DEPTH {
+        subdirs.sort();
+        for sub in subdirs {
+            // A folder named after a SteamID64 holds that account's saves, so
+            // skip it when looking for another owner's (Steam's below GOG's)
+            let name = sub.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let sub_owner = match &owner {
+                Some(owner) if validate_steam_id(name).is_ok() && owner != name => continue,
+                None if validate_steam_id(name).is_ok() => Some(name.to_string()),
+                _ => owner.clone(),
+            };
+            collect_slots(game, profile, &sub, sub_owner, depth + 1, slots);
+        }
+    }
+}
@@ -1,12 +1,27 @@
-#[cfg(target_os = "windows")]
 use aes_gcm::{aead::{AeadCore, KeyInit, OsRng, Aead, Payload}, Aes128Gcm, Nonce, Key};
 use sha2::{Sha256, Digest};
-use anyhow::{Result, anyhow};
+use thiserror::Error;
 
 pub const NONCE_LENGTH: usize = 12;
 pub const TAG_LENGTH: usize = 16;
 pub const NONCE_AND_TAG_TOTAL_LENGTH: usize = NONCE_LENGTH + TAG_LENGTH;
 
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("Input data too short ({0} bytes, expected at least {NONCE_AND_TAG_TOTAL_LENGTH})")]
+    TooShort(usize),
+    #[error("Authentication failed: wrong user ID, game code or file name")]
+    AuthenticationFailed,
+    #[error("Encryption failed")]
+    EncryptionFailed,
+    #[error("Key derivation failed")]
+    KeyDerivation,
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, CryptoError>;
+
 pub struct IdCrypto;
 
 impl IdCrypto {
@@ -17,7 +32,7 @@ impl IdCrypto {
         user_id: &str,
     ) -> Result<Vec<u8>> {
         if input_data.len() < NONCE_AND_TAG_TOTAL_LENGTH {
-            return Err(anyhow!("Input data too short"));
+            return Err(CryptoError::TooShort(input_data.len()));
         }
 
         let nonce_bytes = &input_data[..NONCE_LENGTH];
@@ -32,7 +47,25 @@ impl IdCrypto {
         };
         
         cipher.decrypt(nonce, payload)
-            .map_err(|e| anyhow!("Decryption failed: {}", e))
+            .map_err(|_| CryptoError::AuthenticationFailed)
+    }
+
+    // Like decrypt_file, but also tries the names a renamed or backed-up copy
+    // was likely encrypted under, and returns the name that authenticated
+    pub fn decrypt_file_as(
+        input_data: &[u8],
+        file_name: &str,
+        game_code: &str,
+        user_id: &str,
+    ) -> Result<(Vec<u8>, String)> {
+        for name in name_variants(file_name) {
+            match Self::decrypt_file(input_data, &name, game_code, user_id) {
+                Ok(data) => return Ok((data, name)),
+                Err(CryptoError::AuthenticationFailed) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(CryptoError::AuthenticationFailed)
     }
 
     pub fn encrypt_file(
@@ -50,7 +83,7 @@ impl IdCrypto {
             aad: aad.as_bytes(),
         };
         let ciphertext = cipher.encrypt(&nonce, payload)
-            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+            .map_err(|_| CryptoError::EncryptionFailed)?;
         let mut output = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
         output.extend_from_slice(&nonce);
         output.extend_from_slice(&ciphertext);
@@ -75,7 +108,120 @@ impl IdCrypto {
         hasher.update(game_code.as_bytes());
         hasher.update(file_name.as_bytes());
         let hash = hasher.finalize();
-        let key_bytes = &hash[..16];
+        let key_bytes = hash.get(..16).ok_or(CryptoError::KeyDerivation)?;
         Ok(*Key::<Aes128Gcm>::from_slice(key_bytes))
     }
 }
+
+const BACKUP_SUFFIXES: [&str; 4] = ["-backup", ".bak", ".old", ".orig"];
+const COPY_MARKERS: [&str; 4] = [" - Copy", " - copy", " copy", "_copy"];
+
+// The name itself first, then without a backup suffix or copy marker
+// ("game.details-backup", "game - Copy.details", "game (2).details" all give
+// "game.details"), then the canonical name's own backup
+pub fn name_variants(file_name: &str) -> Vec<String> {
+    let stripped = strip_backup_suffix(file_name);
+    let canonical = strip_copy_marker(stripped);
+    let mut names = vec![file_name.to_string()];
+    for name in [stripped.to_string(), canonical.clone(), format!("{}-backup", canonical)] {
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn strip_backup_suffix(name: &str) -> &str {
+    BACKUP_SUFFIXES.iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .filter(|base| !base.is_empty())
+        .unwrap_or(name)
+}
+
+fn strip_copy_marker(name: &str) -> String {
+    let (mut stem, ext) = match name.find('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    };
+    loop {
+        let before = stem;
+        // Explorer's " (2)" and " - Copy (2)"
+        if let Some(open) = stem.rfind(" (")
+            && stem.ends_with(')')
+            && stem[open + 2..stem.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+            stem = &stem[..open];
+        }
+        if let Some(base) = COPY_MARKERS.iter().find_map(|m| stem.strip_suffix(m)) {
+            stem = base;
+        }
+        if stem == before || stem.is_empty() {
+            break;
+        }
+    }
+    if stem.is_empty() {
+        return name.to_string();
+    }
+    format!("{}{}", stem, ext)
+}
+
+pub fn is_file_encrypted(data: &[u8]) -> bool {
+    if data.len() < 16 {
+        return false;
+    }
+    
+    let text_start = std::str::from_utf8(&data[..16.min(data.len())]).is_ok();
+    if text_start {
+        return false;
+    }
+    
+    let mut byte_counts = [0u32; 256];
+    for &byte in data.iter().take(1024) {
+        byte_counts[byte as usize] += 1;
+    }
+    
+    let len = data.len().min(1024) as f64;
+    let entropy: f64 = byte_counts.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    
+    entropy > 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_variants_start_with_the_name_itself() {
+        assert_eq!(name_variants("game.details"), ["game.details", "game.details-backup"]);
+        assert_eq!(name_variants("game.details-backup"), ["game.details-backup", "game.details"]);
+    }
+
+    #[test]
+    fn name_variants_strip_backup_suffixes() {
+        assert_eq!(name_variants("slot.dat.bak"), ["slot.dat.bak", "slot.dat", "slot.dat-backup"]);
+        assert_eq!(name_variants("slot.dat.orig"), ["slot.dat.orig", "slot.dat", "slot.dat-backup"]);
+    }
+
+    #[test]
+    fn name_variants_strip_copy_markers() {
+        for name in ["game - Copy.details", "game (2).details", "game_copy.details", "game copy.details"] {
+            assert_eq!(name_variants(name), [name, "game.details", "game.details-backup"]);
+        }
+        assert_eq!(
+            name_variants("game - Copy (2).details-backup"),
+            ["game - Copy (2).details-backup", "game - Copy (2).details", "game.details", "game.details-backup"]
+        );
+    }
+
+    #[test]
+    fn name_variants_keep_names_that_are_only_a_marker() {
+        assert_eq!(name_variants("-backup"), ["-backup", "-backup-backup"]);
+        assert_eq!(name_variants(" (2).dat"), [" (2).dat", " (2).dat-backup"]);
+        assert_eq!(name_variants("(2)"), ["(2)", "(2)-backup"]);
+    }
+}
@@ -1,6 +1,5 @@
 #![windows_subsystem = "windows"]
 use eframe::egui;
-mod logic;
 mod app;
 use app::SaveDataApp;
 use eframe::epaint::Vec2;
@@ -24,10 +23,9 @@ fn main() -> Result<(), eframe::Error> {
     let icon = load_icon();
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size(Vec2::new(450.0, 360.0))
-            .with_resizable(false)
-            .with_maximized(false)
-            .with_maximize_button(false)
+            .with_inner_size(Vec2::new(480.0, 560.0))
+            .with_min_inner_size(Vec2::new(450.0, 360.0))
+            .with_resizable(true)
             .with_icon(icon),
         ..Default::default()
     };
@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::batch::{write_synced, BatchOptions, FileResult, Operation};
+
+// Written next to INFO.txt
+pub const MANIFEST_FILE: &str = "INFO.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub tool_version: String,
+    pub timestamp: String,
+    pub operation: String,
+    pub game: String,
+    pub platform: Option<String>,
+    pub game_code: String,
+    // Where a Convert run wrote for, None when that's the source platform
+    #[serde(default)]
+    pub target_platform: Option<String>,
+    #[serde(default)]
+    pub target_game_code: Option<String>,
+    pub source_id: Option<String>,
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub ids_redacted: bool,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    // Relative to the input folder
+    pub path: PathBuf,
+    // Relative to the output folder, None when nothing was written
+    pub output_path: Option<PathBuf>,
+    pub size_before: Option<u64>,
+    pub size_after: Option<u64>,
+    pub sha256_before: Option<String>,
+    pub sha256_after: Option<String>,
+    pub status: String,
+    pub message: String,
+}
+
+impl Manifest {
+    pub fn new(op: &Operation, code: &str, options: &BatchOptions) -> Self {
+        let (source, target) = op.ids();
+        let redact = |id: Option<&str>| id.map(|id| if options.redact_ids { redact_id(id) } else { id.to_string() });
+        Manifest {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: Local::now().to_rfc3339(),
+            operation: op.name().to_string(),
+            game: options.game.clone(),
+            platform: options.platform.as_ref().map(|p| p.label().to_lowercase()),
+            game_code: code.to_string(),
+            target_platform: options.target_platform.as_ref().map(|p| p.label().to_lowercase()),
+            target_game_code: match op {
+                Operation::Convert { to_code, .. } => Some(to_code.clone()),
+                _ => None,
+            },
+            source_id: redact(source),
+            target_id: redact(target),
+            ids_redacted: options.redact_ids,
+            files: Vec::new(),
+        }
+    }
+
+    // Records a file from the bytes now in the input and output folders
+    pub fn add(&mut self, input: &Path, output: &Path, result: &FileResult, output_path: Option<PathBuf>) {
+        let before = fs::read(input.join(&result.path)).ok();
+        let after = output_path.as_ref().and_then(|p| fs::read(output.join(p)).ok());
+        self.files.push(ManifestFile {
+            path: result.path.clone(),
+            size_before: before.as_ref().map(|d| d.len() as u64),
+            size_after: after.as_ref().map(|d| d.len() as u64),
+            sha256_before: before.as_deref().map(sha256_hex),
+            sha256_after: after.as_deref().map(sha256_hex),
+            output_path: after.and(output_path),
+            status: result.status.code().to_string(),
+            message: result.describe(),
+        });
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&ManifestFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    pub fn write(&self, folder: &Path) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_synced(&folder.join(MANIFEST_FILE), json.as_bytes())?;
+        Ok(())
+    }
+}
+
+// Accepts the manifest itself or the folder it was written to
+pub fn read_manifest(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+    let path = if path.is_dir() { path.join(MANIFEST_FILE) } else { path.to_path_buf() };
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid run manifest in {}: {}", path.display(), e))?)
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Keeps the last four characters, enough to tell accounts apart
+pub fn redact_id(id: &str) -> String {
+    let chars: Vec<char> = id.chars().collect();
+    let keep = chars.len().saturating_sub(4);
+    chars.iter().enumerate().map(|(i, c)| if i < keep { '*' } else { *c }).collect()
+}
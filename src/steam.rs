@@ -0,0 +1,250 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::ids::{steam_id_from_account_id, validate_steam_id};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vdf {
+    Value(String),
+    Section(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    // Keys are case-insensitive in Steam's files
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Section(entries) => entries.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v),
+            Vdf::Value(_) => None,
+        }
+    }
+
+    pub fn value(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(Vdf::Value(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, Vdf)] {
+        match self {
+            Vdf::Section(entries) => entries,
+            Vdf::Value(_) => &[],
+        }
+    }
+}
+
+// Text KeyValues as used by Steam's config files: quoted or bare tokens,
+// nested { } sections, // comments and [$PLATFORM] conditionals (ignored)
+pub fn parse_vdf(text: &str) -> Result<Vdf, String> {
+    let tokens = tokenize(text)?;
+    let mut pos = 0;
+    let root = parse_section(&tokens, &mut pos, false)?;
+    Ok(Vdf::Section(root))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(other) => value.push(other),
+                            None => return Err("Unterminated string".to_string()),
+                        },
+                        Some(c) => value.push(c),
+                        None => return Err("Unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c => {
+                let mut value = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '{' | '}' | '"') {
+                        break;
+                    }
+                    value.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Str(value));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_section(tokens: &[Token], pos: &mut usize, nested: bool) -> Result<Vec<(String, Vdf)>, String> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.get(*pos) {
+            Some(Token::Str(key)) => key.clone(),
+            Some(Token::Close) if nested => {
+                *pos += 1;
+                return Ok(entries);
+            }
+            Some(_) => return Err("Expected a key".to_string()),
+            None if nested => return Err("Missing closing brace".to_string()),
+            None => return Ok(entries),
+        };
+        *pos += 1;
+        let value = match tokens.get(*pos) {
+            Some(Token::Str(value)) => Vdf::Value(value.clone()),
+            Some(Token::Open) => {
+                *pos += 1;
+                entries.push((key, Vdf::Section(parse_section(tokens, pos, true)?)));
+                continue;
+            }
+            _ => return Err(format!("Missing value for '{}'", key)),
+        };
+        *pos += 1;
+        entries.push((key, value));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteamAccount {
+    pub steam_id: String,
+    pub account_name: String,
+    pub persona_name: String,
+    pub most_recent: bool,
+    pub timestamp: u64,
+}
+
+impl SteamAccount {
+    pub fn label(&self) -> String {
+        match (self.persona_name.is_empty(), self.account_name.is_empty()) {
+            (false, _) => format!("{} ({})", self.persona_name, self.steam_id),
+            (true, false) => format!("{} ({})", self.account_name, self.steam_id),
+            (true, true) => self.steam_id.clone(),
+        }
+    }
+}
+
+// Accounts listed in config/loginusers.vdf, most recently used first
+pub fn parse_login_users(text: &str) -> Result<Vec<SteamAccount>, String> {
+    let root = parse_vdf(text)?;
+    let users = root.get("users").ok_or("No \"users\" section")?;
+
+    let mut accounts = Vec::new();
+    for (key, user) in users.entries() {
+        // Entries that aren't SteamID64s are not accounts
+        if validate_steam_id(key).is_err() {
+            continue;
+        }
+        accounts.push(SteamAccount {
+            steam_id: key.clone(),
+            account_name: user.value("AccountName").unwrap_or_default().to_string(),
+            persona_name: user.value("PersonaName").unwrap_or_default().to_string(),
+            most_recent: user.value("MostRecent") == Some("1"),
+            timestamp: user.value("Timestamp").and_then(|t| t.parse().ok()).unwrap_or(0),
+        });
+    }
+    accounts.sort_by(|a, b| b.most_recent.cmp(&a.most_recent).then(b.timestamp.cmp(&a.timestamp)));
+    Ok(accounts)
+}
+
+pub fn read_login_users(path: &Path) -> Result<Vec<SteamAccount>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_login_users(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+pub fn steam_roots() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = env::var_os(var) {
+                candidates.push(PathBuf::from(dir).join("Steam"));
+            }
+        }
+    } else if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        candidates.push(home.join(".steam/steam"));
+        candidates.push(home.join(".local/share/Steam"));
+        candidates.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        candidates.push(home.join("Library/Application Support/Steam"));
+    }
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for dir in candidates {
+        // ~/.steam/steam is usually a symlink to one of the other locations
+        if let Ok(real) = fs::canonicalize(&dir)
+            && real.is_dir()
+            && !roots.contains(&real) {
+            roots.push(real);
+        }
+    }
+    roots
+}
+
+pub fn userdata_account_ids(root: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(root.join("userdata")) else {
+        return Vec::new();
+    };
+
+    let mut ids: Vec<u32> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().and_then(|n| n.parse().ok()))
+        .filter(|&id| id != 0)
+        .collect();
+    ids.sort();
+    ids
+}
+
+// Accounts that signed in to Steam on this machine, from loginusers.vdf and
+// the userdata folders (which have no names)
+pub fn local_steam_accounts() -> Vec<SteamAccount> {
+    let mut accounts: Vec<SteamAccount> = Vec::new();
+    for root in steam_roots() {
+        let known = read_login_users(&root.join("config/loginusers.vdf")).unwrap_or_default();
+        let unnamed = userdata_account_ids(&root).into_iter().map(|account| SteamAccount {
+            steam_id: steam_id_from_account_id(account),
+            account_name: String::new(),
+            persona_name: String::new(),
+            most_recent: false,
+            timestamp: 0,
+        });
+        for account in known.into_iter().chain(unnamed) {
+            if !accounts.iter().any(|a| a.steam_id == account.steam_id) {
+                accounts.push(account);
+            }
+        }
+    }
+    accounts
+}
+
+pub fn local_steam_ids() -> Vec<String> {
+    local_steam_accounts().into_iter().map(|a| a.steam_id).collect()
+}
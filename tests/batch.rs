@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use resigner::batch::{self, BatchOptions, ConflictPolicy, FileStatus, Operation};
+use resigner::IdCrypto;
+
+const CODE: &str = "MANCUBUS";
+const ID: &str = "76561198000000000";
+
+// A fresh folder under the system temp dir, removed first if a previous run left it
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("resigner-batch-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Writes `file` encrypted under `key_name`
+fn save(dir: &Path, file: &str, key_name: &str) {
+    let data = IdCrypto::encrypt_file(b"progress", key_name, CODE, ID).unwrap();
+    fs::write(dir.join(file), data).unwrap();
+}
+
+fn decrypts_as(path: &Path, key_name: &str) -> bool {
+    IdCrypto::decrypt_file(&fs::read(path).unwrap(), key_name, CODE, ID).is_ok()
+}
+
+fn rename(from: &str, to: &str) -> Operation {
+    Operation::Rename { old_id: ID.to_string(), new_id: ID.to_string(), from: from.to_string(), to: to.to_string() }
+}
+
+#[test]
+fn rename_moves_the_key_name_to_the_new_slot() {
+    let root = temp_dir("rename");
+    let input = root.join("in");
+    fs::create_dir_all(&input).unwrap();
+    save(&input, "game.details", "game.details");
+    // A backup copy, still bound to the name it was copied from
+    save(&input, "game.details-backup", "game.details");
+    let output = root.join("out");
+
+    let report = batch::process(&rename("game", "game2"), &input, &output, CODE, &BatchOptions::default()).unwrap();
+    assert!(report.is_success(), "{}", report.message);
+
+    assert!(decrypts_as(&output.join("game2.details"), "game2.details"));
+    assert!(decrypts_as(&output.join("game2.details-backup"), "game2.details"));
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn rename_moves_a_logical_name_to_the_new_slot() {
+    let root = temp_dir("rename-logical");
+    let input = root.join("in");
+    fs::create_dir_all(&input).unwrap();
+    save(&input, "game.details-backup", "game.details");
+    let output = root.join("out");
+
+    let options = BatchOptions {
+        logical_names: [(PathBuf::from("game.details-backup"), "game.details".to_string())].into(),
+        ..BatchOptions::default()
+    };
+    let report = batch::process(&rename("game", "game2"), &input, &output, CODE, &options).unwrap();
+    assert!(report.is_success(), "{}", report.message);
+    assert_eq!(report.files[0].status, FileStatus::Ok);
+    assert_eq!(report.files[0].key_name.as_deref(), Some("game2.details"));
+
+    let renamed = output.join("game2.details-backup");
+    assert!(decrypts_as(&renamed, "game2.details"));
+    assert!(!decrypts_as(&renamed, "game.details"));
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn verify_fails_saves_that_only_decrypt_under_another_name() {
+    let root = temp_dir("verify");
+    let input = root.join("in");
+    fs::create_dir_all(&input).unwrap();
+    save(&input, "game.details", "game.details");
+    save(&input, "game - Copy.details", "game.details");
+
+    let op = Operation::Verify { id: ID.to_string() };
+    let options = BatchOptions { continue_on_error: true, ..BatchOptions::default() };
+    let report = batch::process(&op, &input, &root.join("out"), CODE, &options).unwrap();
+    assert!(!report.is_success());
+    assert!(!root.join("out").exists());
+
+    let status = |name: &str| report.files.iter().find(|f| f.path == Path::new(name)).unwrap().status.clone();
+    assert_eq!(status("game.details"), FileStatus::Ok);
+    assert_eq!(status("game - Copy.details"), FileStatus::WrongName("game.details".to_string()));
+
+    // Unless it's told that's the name to check
+    let options = BatchOptions {
+        logical_names: [(PathBuf::from("game - Copy.details"), "game.details".to_string())].into(),
+        ..BatchOptions::default()
+    };
+    let report = batch::process(&op, &input, &root.join("out"), CODE, &options).unwrap();
+    assert!(report.is_success(), "{}", report.message);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn plans_in_place_runs_into_the_input_folder() {
+    let root = temp_dir("plan-in-place");
+    let input = root.join("in");
+    fs::create_dir_all(&input).unwrap();
+    save(&input, "game.details", "game.details");
+    fs::write(input.join("notes.txt"), b"not a save").unwrap();
+
+    // The conflict policy is about an output folder, which an in-place run doesn't have
+    let options = BatchOptions { conflicts: ConflictPolicy::NewFolder, copy_skipped: true, ..BatchOptions::default() };
+    let plan = batch::plan_in_place(&rename("game", "game2"), &input, &options).unwrap();
+
+    assert_eq!(plan.output, input);
+    assert_eq!(plan.files[0].target.as_deref(), Some(input.join("game2.details").as_path()));
+    assert!(plan.copied.is_empty());
+    assert_eq!(plan.skipped, [input.join("notes.txt")]);
+    fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+use resigner::games::{FileKind, Registry};
+
+fn kinds(slug: &str, paths: &[&str]) -> Vec<FileKind> {
+    let registry = Registry::builtin();
+    let game = registry.get(slug).unwrap();
+    paths.iter().map(|p| game.files.classify(Path::new(p))).collect()
+}
+
+#[test]
+fn doom_eternal_saves() {
+    use FileKind::*;
+    let paths = [
+        "GAME-AUTOSAVE0/game.details",
+        "GAME-AUTOSAVE0/game_duration.dat",
+        "PROFILE/profile.bin",
+        "steam_autocloud.vdf",
+        "INFO.json",
+        // DOOM Eternal keeps no -backup copies, so these aren't its saves
+        "GAME-AUTOSAVE0/game.details-backup",
+        "a.bin",
+    ];
+    assert_eq!(kinds("doom-eternal", &paths), [Encrypted, Encrypted, Encrypted, Plaintext, Ignored, Unknown, Unknown]);
+}
+
+#[test]
+fn doom_the_dark_ages_saves() {
+    use FileKind::*;
+    let paths = [
+        "GAME-AUTOSAVE0/game.details",
+        "GAME-AUTOSAVE0/game.details-backup",
+        "GAME-AUTOSAVE0/game_duration.dat-backup",
+        "PROFILE/profile.bin",
+        "steam_autocloud.vdf",
+        "a.bin",
+    ];
+    assert_eq!(kinds("doom-the-dark-ages", &paths), [Encrypted, Encrypted, Encrypted, Encrypted, Plaintext, Unknown]);
+}
+
+#[test]
+fn indiana_jones_saves() {
+    use FileKind::*;
+    let paths = [
+        "GAME-MANUALSAVE0/game.details",
+        "GAME-MANUALSAVE0/game.details-backup",
+        "GAME-MANUALSAVE0/game_duration.dat",
+        "GAME-MANUALSAVE0/game_duration.dat-backup",
+        "PROFILE/profile.bin",
+        "steam_autocloud.vdf",
+        "Thumbs.db",
+        "screenshot.bin",
+    ];
+    assert_eq!(kinds("indiana-jones", &paths), [Encrypted, Encrypted, Encrypted, Encrypted, Encrypted, Plaintext, Ignored, Unknown]);
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use resigner::batch::{BatchOptions, Operation};
+use resigner::history::{self, History, RunRecord};
+use resigner::IdCrypto;
+
+const CODE: &str = "MANCUBUS";
+const OLD_ID: &str = "76561198000000000";
+
+// A fresh folder under the system temp dir, removed first if a previous run left it
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("resigner-history-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// game.details and sub/slot.dat encrypted for OLD_ID
+fn save_folder(root: &Path) -> PathBuf {
+    let input = root.join("GAME-AUTOSAVE0");
+    fs::create_dir_all(input.join("sub")).unwrap();
+    for (path, name) in [("game.details", "game.details"), ("sub/slot.dat", "slot.dat")] {
+        let data = IdCrypto::encrypt_file(format!("progress in {}", name).as_bytes(), name, CODE, OLD_ID).unwrap();
+        fs::write(input.join(path), data).unwrap();
+    }
+    input
+}
+
+fn resign(new_id: &str) -> Operation {
+    Operation::Resign { old_id: OLD_ID.to_string(), new_id: new_id.to_string() }
+}
+
+// Every file below `dir` with its bytes, sorted by path
+fn snapshot(dir: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in fs::read_dir(&current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push((path.strip_prefix(dir).unwrap().to_path_buf(), fs::read(&path).unwrap()));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn run(op: &Operation, input: &Path, output: &Path, in_place: bool) -> RunRecord {
+    let (report, run) = history::run(op, input, output, CODE, &BatchOptions::default(), in_place).unwrap();
+    assert!(report.is_success(), "{}", report.message);
+    run
+}
+
+#[test]
+fn restores_an_overwritten_output_folder() {
+    let root = temp_dir("overwrite");
+    let input = save_folder(&root);
+    let output = root.join("out");
+
+    run(&resign("76561198000000001"), &input, &output, false);
+    let before = snapshot(&output);
+    let second = run(&resign("76561198000000002"), &input, &output, false);
+    assert_ne!(snapshot(&output), before);
+
+    history::restore(&second, false).unwrap();
+    // Saves, INFO.txt and INFO.json are all back as the first run left them
+    assert_eq!(snapshot(&output), before);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn restores_an_in_place_run() {
+    let root = temp_dir("in-place");
+    let input = save_folder(&root);
+    let before = snapshot(&input);
+
+    let record = run(&resign("76561198000000001"), &input, &input, true);
+    assert_ne!(snapshot(&input), before);
+    assert!(record.backup.as_ref().is_some_and(|b| b.is_dir()));
+
+    history::restore(&record, false).unwrap();
+    assert_eq!(snapshot(&input), before);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn restores_an_in_place_rename() {
+    let root = temp_dir("rename");
+    let input = save_folder(&root);
+    let before = snapshot(&input);
+
+    let op = Operation::Rename {
+        old_id: OLD_ID.to_string(),
+        new_id: OLD_ID.to_string(),
+        from: "game".to_string(),
+        to: "game2".to_string(),
+    };
+    let record = run(&op, &input, &input, true);
+    assert!(input.join("game2.details").exists());
+    assert!(!input.join("game.details").exists());
+
+    history::restore(&record, false).unwrap();
+    assert_eq!(snapshot(&input), before);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn refuses_stale_and_repeated_restores() {
+    let root = temp_dir("stale");
+    let input = save_folder(&root);
+    let output = root.join("out");
+    let history_file = root.join("history.json");
+
+    let first = History::append(&history_file, run(&resign("76561198000000001"), &input, &output, false)).unwrap();
+    let second = History::append(&history_file, run(&resign("76561198000000002"), &input, &output, false)).unwrap();
+    let newer = snapshot(&output);
+    let load = |id: &str| History::load(&history_file).unwrap().get(id).unwrap().clone();
+
+    // The second run overwrote everything the first one wrote
+    let err = history::restore(&load(&first), false).unwrap_err().to_string();
+    assert!(err.contains("changed since run"), "{}", err);
+    assert_eq!(snapshot(&output), newer);
+
+    history::restore(&load(&second), false).unwrap();
+    History::mark_restored(&history_file, &second).unwrap();
+    let err = history::restore(&load(&second), false).unwrap_err().to_string();
+    assert!(err.contains("already restored"), "{}", err);
+
+    // With the second run undone, the first one is current again
+    history::restore(&load(&first), false).unwrap();
+    assert!(!output.exists());
+    fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::PathBuf;
+use resigner::games::{Registry, Platform};
+use resigner::locate::{find_save_slots_with, find_slots_in, Folders};
+
+#[test]
+fn finds_saves_in_a_proton_prefix() {
+    let root = std::env::temp_dir().join(format!("resigner-locate-{}", std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    let user = root.join("steamapps/compatdata/782330/pfx/drive_c/users/steamuser");
+    let slot = user.join("Saved Games/id Software/DOOMEternal/base/savegame/76561198000000000/GAME-AUTOSAVE0");
+    fs::create_dir_all(&slot).unwrap();
+    fs::write(slot.join("game.details"), b"not a real save").unwrap();
+    // Not named after a SteamID64, so not an account's saves
+    fs::create_dir_all(user.join("Saved Games/id Software/DOOMEternal/base/savegame/backup")).unwrap();
+
+    let registry = Registry::builtin();
+    let game = registry.get("doom-eternal").unwrap();
+    let folders = Folders {
+        saved_games: vec![user.join("Saved Games")],
+        documents: vec![user.join("Documents")],
+        ..Default::default()
+    };
+    let slots = find_slots_in(game, game.platform(&Platform::Steam).unwrap(), &folders);
+
+    assert_eq!(slots.len(), 1);
+    assert_eq!(slots[0].path, PathBuf::from(&slot));
+    assert_eq!(slots[0].owner.as_deref(), Some("76561198000000000"));
+    assert_eq!(slots[0].files, 1);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn keeps_steam_saves_out_of_the_gog_folder() {
+    let root = std::env::temp_dir().join(format!("resigner-locate-gog-{}", std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    let savegame = root.join("Saved Games/id Software/DOOMEternal/base/savegame");
+    let gog = savegame.join("GAME-AUTOSAVE0");
+    let steam = savegame.join("76561198000000000/GAME-AUTOSAVE0");
+    for slot in [&gog, &steam] {
+        fs::create_dir_all(slot).unwrap();
+        fs::write(slot.join("game.details"), b"not a real save").unwrap();
+    }
+
+    let registry = Registry::builtin();
+    let game = registry.get("doom-eternal").unwrap();
+    let folders = Folders { saved_games: vec![root.join("Saved Games")], ..Default::default() };
+    let slots = find_save_slots_with(game, |_| folders.clone());
+
+    let found: Vec<_> = slots.iter().map(|s| (s.path.clone(), s.platform.clone(), s.owner.as_deref())).collect();
+    assert_eq!(found, [
+        (steam, Platform::Steam, Some("76561198000000000")),
+        (gog, Platform::GOG, Some("GogUser")),
+    ]);
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn gives_a_hardcoded_id_folder_to_its_release() {
+    let root = std::env::temp_dir().join(format!("resigner-locate-hardcoded-{}", std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    let savegame = root.join("Saved Games/MachineGames/TheGreatCircle/base/savegame");
+    let gog = savegame.join("76561197960265729");
+    let steam = savegame.join("76561198000000000");
+    for slot in [&gog, &steam] {
+        fs::create_dir_all(slot).unwrap();
+        fs::write(slot.join("game.details"), b"not a real save").unwrap();
+    }
+
+    let registry = Registry::builtin();
+    let game = registry.get("indiana-jones").unwrap();
+    let folders = Folders { saved_games: vec![root.join("Saved Games")], ..Default::default() };
+    let slots = find_save_slots_with(game, |_| folders.clone());
+
+    let found: Vec<_> = slots.iter().map(|s| (s.path.clone(), s.platform.clone())).collect();
+    assert_eq!(found, [(gog, Platform::GOG), (steam, Platform::Steam)]);
+    fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use resigner::batch::{self, BatchOptions, ConflictPolicy, Operation};
+use resigner::games::Platform;
+use resigner::manifest::{read_manifest, sha256_hex, ManifestFile, MANIFEST_FILE};
+use resigner::IdCrypto;
+
+const STEAM_ID: &str = "76561198000000000";
+
+// A fresh folder under the system temp dir, removed first if a previous run left it
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("resigner-manifest-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// game.details and slot.dat encrypted for DOOM Eternal on Steam, plus a file no profile lists
+fn save_folder(root: &Path) -> PathBuf {
+    let input = root.join("in");
+    fs::create_dir_all(&input).unwrap();
+    for name in ["game.details", "slot.dat"] {
+        let data = IdCrypto::encrypt_file(b"progress", name, "MANCUBUS", STEAM_ID).unwrap();
+        fs::write(input.join(name), data).unwrap();
+    }
+    fs::write(input.join("notes.txt"), b"not a save").unwrap();
+    input
+}
+
+fn file<'a>(files: &'a [ManifestFile], path: &str) -> &'a ManifestFile {
+    files.iter().find(|f| f.path == Path::new(path)).unwrap()
+}
+
+#[test]
+fn records_a_conversion_and_reads_it_back() {
+    let root = temp_dir("convert");
+    let input = save_folder(&root);
+    let output = root.join("out");
+    let op = Operation::Convert { old_id: STEAM_ID.to_string(), new_id: "GogUser".to_string(), to_code: "ARACHNOTRON".to_string() };
+    let options = BatchOptions {
+        game: "doom-eternal".to_string(),
+        platform: Some(Platform::Steam),
+        target_platform: Some(Platform::GOG),
+        ..BatchOptions::default()
+    };
+    batch::process(&op, &input, &output, "MANCUBUS", &options).unwrap();
+
+    // The folder and the file itself both work
+    let manifest = read_manifest(&output).unwrap();
+    assert_eq!(read_manifest(&output.join(MANIFEST_FILE)).unwrap(), manifest);
+
+    assert_eq!(manifest.operation, "convert");
+    assert_eq!(manifest.game, "doom-eternal");
+    assert_eq!(manifest.platform.as_deref(), Some("steam"));
+    assert_eq!(manifest.game_code, "MANCUBUS");
+    assert_eq!(manifest.target_platform.as_deref(), Some("gog"));
+    assert_eq!(manifest.target_game_code.as_deref(), Some("ARACHNOTRON"));
+    assert_eq!(manifest.source_id.as_deref(), Some(STEAM_ID));
+    assert_eq!(manifest.target_id.as_deref(), Some("GogUser"));
+    assert!(!manifest.ids_redacted);
+
+    let details = file(&manifest.files, "game.details");
+    let written = fs::read(output.join("game.details")).unwrap();
+    assert_eq!(details.status, "ok");
+    assert_eq!(details.output_path.as_deref(), Some(Path::new("game.details")));
+    assert_eq!(details.sha256_before.as_deref(), Some(sha256_hex(&fs::read(input.join("game.details")).unwrap()).as_str()));
+    assert_eq!(details.sha256_after.as_deref(), Some(sha256_hex(&written).as_str()));
+    assert_eq!(details.size_after, Some(written.len() as u64));
+
+    // Skipped files are listed, with nothing written for them
+    let notes = file(&manifest.files, "notes.txt");
+    assert_eq!(notes.status, "skipped");
+    assert_eq!(notes.output_path, None);
+    assert_eq!(notes.sha256_after, None);
+    assert!(notes.sha256_before.is_some());
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn records_kept_files_and_redacted_ids() {
+    let root = temp_dir("kept");
+    let input = save_folder(&root);
+    let output = root.join("out");
+    let op = Operation::Resign { old_id: STEAM_ID.to_string(), new_id: "76561198000000001".to_string() };
+    batch::process(&op, &input, &output, "MANCUBUS", &BatchOptions::default()).unwrap();
+    let kept = fs::read(output.join("slot.dat")).unwrap();
+    fs::remove_file(output.join("game.details")).unwrap();
+
+    let options = BatchOptions { conflicts: ConflictPolicy::Skip, redact_ids: true, ..BatchOptions::default() };
+    batch::process(&op, &input, &output, "MANCUBUS", &options).unwrap();
+    let manifest = read_manifest(&output).unwrap();
+
+    assert!(manifest.ids_redacted);
+    assert_eq!(manifest.source_id.as_deref(), Some("*************0000"));
+    assert_eq!(manifest.target_id.as_deref(), Some("*************0001"));
+    assert_eq!(manifest.target_platform, None);
+    assert_eq!(manifest.target_game_code, None);
+
+    // The existing slot.dat was left alone, game.details written again
+    let slot = file(&manifest.files, "slot.dat");
+    assert_eq!(slot.status, "exists");
+    assert_eq!(slot.output_path, None);
+    assert_eq!(slot.sha256_after, None);
+    assert_eq!(fs::read(output.join("slot.dat")).unwrap(), kept);
+    let details = file(&manifest.files, "game.details");
+    assert_eq!(details.status, "ok");
+    assert!(details.sha256_after.is_some());
+    fs::remove_dir_all(&root).ok();
+}
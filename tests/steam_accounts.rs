@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use resigner::steam::{parse_login_users, parse_vdf, read_login_users};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[test]
+fn reads_accounts_most_recent_first() {
+    let accounts = read_login_users(&fixture("loginusers.vdf")).unwrap();
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].steam_id, "76561198000000000");
+    assert_eq!(accounts[0].account_name, "doomslayer");
+    assert_eq!(accounts[0].persona_name, "Rip \"and\" Tear");
+    assert!(accounts[0].most_recent);
+    assert_eq!(accounts[1].steam_id, "76561197960287930");
+    assert_eq!(accounts[1].persona_name, "Rabscuttle");
+    assert_eq!(accounts[1].timestamp, 1700000000);
+    assert_eq!(accounts[1].label(), "Rabscuttle (76561197960287930)");
+}
+
+#[test]
+fn tolerates_hand_edited_files() {
+    let accounts = read_login_users(&fixture("loginusers_messy.vdf")).unwrap();
+    let ids: Vec<&str> = accounts.iter().map(|a| a.steam_id.as_str()).collect();
+
+    // Newest timestamp first, the "0" entry is not an account
+    assert_eq!(ids, ["76561198000000002", "76561198000000001", "76561198000000003"]);
+    assert_eq!(accounts[0].persona_name, "Path\\With\\Backslashes");
+    assert_eq!(accounts[1].account_name, "nopersona");
+    assert_eq!(accounts[1].label(), "nopersona (76561198000000001)");
+    assert_eq!(accounts[2].persona_name, "bare");
+}
+
+#[test]
+fn rejects_truncated_files() {
+    assert!(read_login_users(&fixture("loginusers_truncated.vdf")).is_err());
+    assert!(read_login_users(&fixture("missing.vdf")).is_err());
+}
+
+#[test]
+fn requires_users_section() {
+    assert!(parse_login_users("\"config\" { }").is_err());
+    assert_eq!(parse_login_users("\"users\" { }").unwrap(), Vec::new());
+}
+
+#[test]
+fn parses_nested_sections() {
+    let vdf = parse_vdf("\"a\" { \"b\" { \"c\" \"1\" } \"d\" \"2\" }").unwrap();
+    let a = vdf.get("A").unwrap();
+
+    assert_eq!(a.get("b").and_then(|b| b.value("c")), Some("1"));
+    assert_eq!(a.value("d"), Some("2"));
+    assert_eq!(a.value("b"), None);
+    assert!(vdf.get("missing").is_none());
+    assert!(parse_vdf("\"a\" { \"b\" \"1\"").is_err());
+    assert!(parse_vdf("\"a\"").is_err());
+}